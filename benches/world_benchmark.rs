@@ -4,14 +4,11 @@ extern crate criterion;
 use criterion::Criterion;
 
 use gameoflife::rle;
+use gameoflife::search::SoupSearch;
 use gameoflife::utils::*;
 use gameoflife::world::*;
 
-fn lwss_tagalong_bench(c: &mut Criterion) {
-    let mut world = World::new();
-
-    world.action(|world| {
-        let life_content = r#"
+const LWSS_TAGALONG: &str = r#"
 #N LWSS tagalong
 #O David Bell
 #C A tagalong for two lightweight, middleweight, or heavyweight spaces
@@ -22,24 +19,163 @@ x = 25, y = 19, rule = b3/s23
 9b2obobobo2b5o$bo2bo2b2o2bo3b3o2bob2ob$6bo2bob2o12b$6bo4b2o12b$6bo2bob
 2o12b$bo2bo2b2o2bo3b3o2bob2ob$o9b2obobobo2b5o$o3bo5b2ob2obobob5o$4o8bo
 3bob2o5b$13bo11b$13bo2bob2o5b$18b4o3b$21bo!
-            "#;
+"#;
+
+const GOSPER_GLIDER_GUN: &str = r#"
+#N Gosper glider gun
+x = 36, y = 9, rule = B3/S23
+24bo11b$22bobo11b$12b2o6b2o12b2o$11bo3bo4b2o12b2o$2o8bo5bo3b2o14b$2o8b
+o3bob2o4bobo11b$10bo5bo7bo11b$11bo3bo20b$12b2o!
+"#;
+
+fn world_from_rle(content: &str) -> World {
+    let mut world = World::new();
 
+    world.action(|world| {
         let mut placemaker = WorldLifePlaceMaker::new(Coord(0, 0), world);
-        let _ = rle::parse(&life_content, &mut placemaker).unwrap();
+        rle::parse(content, &mut placemaker).unwrap();
+    });
+
+    world
+}
+
+fn evolve(world: &mut World, generations: usize) {
+    for _ in 0..generations {
+        world.evolve().unwrap();
+    }
+}
+
+fn lwss_tagalong_bench(c: &mut Criterion) {
+    c.bench_function("LWSS tagalong, 1000 generations", |b| {
+        b.iter_with_setup(
+            || world_from_rle(LWSS_TAGALONG),
+            |mut world| evolve(&mut world, 1000),
+        )
+    });
+}
+
+/// The gun keeps adding gliders, so the pattern keeps growing
+fn gosper_gun_bench(c: &mut Criterion) {
+    c.bench_function("Gosper glider gun, 10000 generations", |b| {
+        b.iter_with_setup(
+            || world_from_rle(GOSPER_GLIDER_GUN),
+            |mut world| evolve(&mut world, 10000),
+        )
+    });
+}
+
+fn soup_world(engine: Engine) -> World {
+    soup_world_with_rule(engine, "B3/S23")
+}
+
+fn soup_world_with_rule(engine: Engine, rule: &str) -> World {
+    let mut search = SoupSearch::new(1);
+    search.soup_size = 64;
+
+    let mut world = World::with_rule(rule.parse().unwrap());
+    world.set_engine(engine);
+    world.action(|world| {
+        for c in search.soup(0) {
+            world.make_alive(c);
+        }
     });
 
-    c.bench_function("do 1000 iterations", |b| {
+    world
+}
+
+fn engines_bench(c: &mut Criterion) {
+    for engine in &[Engine::Sparse, Engine::Tiled] {
+        let engine = *engine;
+
+        c.bench_function(
+            &format!("{:?} engine, 100 generations of a soup", engine),
+            move |b| b.iter_with_setup(|| soup_world(engine), |mut world| evolve(&mut world, 100)),
+        );
+    }
+}
+
+/// Rules only the sparse engine runs, which count every neighboor of every
+/// live cell each generation
+fn sparse_rules_bench(c: &mut Criterion) {
+    for rule in &["B2a/S23", "B2/S/C3", "B2/S3H"] {
+        let rule = *rule;
+
+        c.bench_function(&format!("{}, 100 generations of a soup", rule), move |b| {
+            b.iter_with_setup(
+                || soup_world_with_rule(Engine::Sparse, rule),
+                |mut world| evolve(&mut world, 100),
+            )
+        });
+    }
+}
+
+/// Windows the size of a terminal, all over a soup that spread for a while
+fn live_cells_bench(c: &mut Criterion) {
+    let mut world = soup_world(Engine::Tiled);
+    evolve(&mut world, 500);
+
+    let (min, max) = world.bounding_box().unwrap();
+    let mut cells = Coords::new();
+
+    // the first query builds the index
+    world.live_cells(&Window::new(min.0, min.1, 80, 24), &mut cells);
+
+    c.bench_function("live cells of 100 terminal-sized windows", move |b| {
         b.iter(|| {
-            let mut world = World::new();
+            let mut cells = Coords::new();
 
-            for n in 0..1000 {
-                world.evolve();
+            for i in 0..100 {
+                let x = min.0 + (max.0 - min.0) * (i % 10) / 10;
+                let y = min.1 + (max.1 - min.1) * (i / 10) / 10;
+                world.live_cells(&Window::new(x, y, 80, 24), &mut cells);
             }
 
-            ()
+            cells
         })
     });
 }
 
-criterion_group!(benches, lwss_tagalong_bench);
-criterion_main!(benches);
+struct CountingPlaceMaker {
+    cells: usize,
+}
+
+impl rle::LifePlaceMaker for CountingPlaceMaker {
+    fn make_cell_alive(&mut self, _: Coord) {
+        self.cells += 1;
+    }
+}
+
+/// A 512x512 soup, which has a short run for almost every cell
+fn rle_parsing_bench(c: &mut Criterion) {
+    let mut search = SoupSearch::new(1);
+    search.soup_size = 512;
+
+    let mut content = Vec::new();
+    rle::LreWriter::new()
+        .write(&search.soup(0), &mut content)
+        .unwrap();
+    let content = String::from_utf8(content).unwrap();
+
+    c.bench_function("parse a 512x512 soup", move |b| {
+        b.iter(|| {
+            let mut placemaker = CountingPlaceMaker { cells: 0 };
+            rle::parse(&content, &mut placemaker).unwrap();
+            placemaker.cells
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    lwss_tagalong_bench,
+    engines_bench,
+    sparse_rules_bench,
+    live_cells_bench,
+    rle_parsing_bench
+);
+criterion_group! {
+    name = slow_benches;
+    config = Criterion::default().sample_size(10);
+    targets = gosper_gun_bench
+}
+criterion_main!(benches, slow_benches);
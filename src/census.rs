@@ -0,0 +1,505 @@
+use crate::common;
+use crate::world::{Coord, Coords, World};
+use std::collections::{BTreeMap, HashMap};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn coords(cells: &[(common::Int, common::Int)]) -> Coords {
+        cells.iter().map(|c| Coord(c.0, c.1)).collect()
+    }
+
+    #[test]
+    fn still_lifes_codes() {
+        let block = coords(&[(0, 0), (1, 0), (0, 1), (1, 1)]);
+        assert_eq!(classify(&block), "xs4_33");
+
+        let beehive = coords(&[(1, 0), (2, 0), (0, 1), (3, 1), (1, 2), (2, 2)]);
+        assert_eq!(classify(&beehive), "xs6_696");
+
+        let boat = coords(&[(0, 0), (1, 0), (0, 1), (2, 1), (1, 2)]);
+        assert_eq!(classify(&boat), "xs5_253");
+    }
+
+    #[test]
+    fn oscillator_and_spaceship_codes() {
+        let blinker = coords(&[(10, 10), (11, 10), (12, 10)]);
+        assert_eq!(classify(&blinker), "xp2_7");
+
+        let glider = coords(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        assert_eq!(classify(&glider), "xq4_153");
+    }
+
+    #[test]
+    fn object_that_dies_is_pathological() {
+        let domino = coords(&[(0, 0), (1, 0)]);
+        assert_eq!(classify(&domino), PATHOLOGICAL);
+    }
+
+    #[test]
+    fn separate_far_apart_objects() {
+        let mut cells = coords(&[(0, 0), (1, 0), (0, 1), (1, 1), (10, 10), (10, 11), (10, 12)]);
+        cells.sort();
+
+        let objects = separate(&cells);
+
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].len(), 4);
+        assert_eq!(objects[1].len(), 3);
+    }
+
+    #[test]
+    fn separate_close_but_independent_objects() {
+        let mut cells = coords(&[(0, 0), (1, 0), (2, 0), (4, 2), (4, 3), (4, 4)]);
+        cells.sort();
+
+        assert_eq!(separate(&cells).len(), 2);
+    }
+
+    #[test]
+    fn keep_together_parts_of_the_same_object() {
+        let mut carrier = coords(&[(0, 0), (1, 0), (0, 1), (3, 1), (2, 2), (3, 2)]);
+        carrier.sort();
+
+        assert_eq!(separate(&carrier).len(), 1);
+        assert_eq!(classify(&carrier), "xs6_39c");
+    }
+
+    #[test]
+    fn census_of_block_and_blinker() {
+        let mut world = World::new();
+        world.action(|world| {
+            for c in coords(&[(0, 0), (1, 0), (0, 1), (1, 1), (10, 10), (10, 11), (10, 12)]) {
+                world.make_alive(c);
+            }
+        });
+
+        let census = Census::of(world.cells());
+
+        assert_eq!(census.counts().get("xs4_33"), Some(&1));
+        assert_eq!(census.counts().get("xp2_7"), Some(&1));
+        assert!(census.counts().keys().all(|code| is_common(code)));
+    }
+
+    #[test]
+    fn periodicity_of_spaceships_and_oscillators() {
+        let glider = coords(&[(1, 0), (2, 1), (0, 2), (1, 2), (2, 2)]);
+        let glider = find_periodicity(&mut world_of(&glider), 100).unwrap();
+
+        assert_eq!((glider.start, glider.period), (0, 4));
+        assert_eq!(glider.displacement, Coord(1, 1));
+        assert_eq!(glider.speed(), Some("c/4 diagonal".to_string()));
+
+        let lwss = coords(&[
+            (1, 0),
+            (4, 0),
+            (0, 1),
+            (0, 2),
+            (4, 2),
+            (0, 3),
+            (1, 3),
+            (2, 3),
+            (3, 3),
+        ]);
+        let lwss = find_periodicity(&mut world_of(&lwss), 100).unwrap();
+
+        assert_eq!(lwss.period, 4);
+        assert_eq!(lwss.speed(), Some("c/2 orthogonal".to_string()));
+
+        let blinker = coords(&[(0, 0), (1, 0), (2, 0)]);
+        let blinker = find_periodicity(&mut world_of(&blinker), 100).unwrap();
+
+        assert_eq!(blinker.period, 2);
+        assert_eq!(blinker.speed(), None);
+    }
+
+    #[test]
+    fn periodicity_after_a_transient() {
+        // a pre-block becomes a block
+        let cells = coords(&[(0, 0), (1, 0), (0, 1)]);
+        let periodicity = find_periodicity(&mut world_of(&cells), 100).unwrap();
+
+        assert_eq!((periodicity.start, periodicity.period), (1, 1));
+
+        let r_pentomino = coords(&[(1, 0), (2, 0), (0, 1), (1, 1), (1, 2)]);
+        assert_eq!(find_periodicity(&mut world_of(&r_pentomino), 100), None);
+    }
+}
+
+/// Code given to objects that don't settle into a known period in isolation
+pub const PATHOLOGICAL: &str = "PATHOLOGICAL";
+
+/// Largest period looked for when classifying an object
+const MAX_PERIOD: usize = 60;
+
+/// Distance (in both axis) under which two live cells belong to the same object
+const OBJECT_DISTANCE: common::Int = 2;
+
+/// Generations two touching objects are evolved to check they don't interact
+const INDEPENDENCE_GENERATIONS: usize = 12;
+
+/// Codes of the objects that show up in nearly every soup
+const COMMON_OBJECTS: [&str; 14] = [
+    "xs4_33", "xs6_696", "xp2_7", "xs7_2596", "xs5_253", "xs4_252", "xs6_356", "xs8_6996",
+    "xq4_153", "xs7_25ac", "xs6_25a4", "xp2_7e", "xp2_318c", "xs6_39c",
+];
+
+pub fn is_common(code: &str) -> bool {
+    COMMON_OBJECTS.contains(&code)
+}
+
+/// Object counts of a stabilized pattern, keyed by apgcode
+#[derive(Debug, Default, Clone)]
+pub struct Census {
+    counts: BTreeMap<String, usize>,
+}
+
+impl Census {
+    pub fn new() -> Self {
+        Census {
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Takes the census of a set of cells sorted by `Coord`
+    pub fn of(cells: &[Coord]) -> Self {
+        let mut census = Census::new();
+
+        for object in separate(cells) {
+            census.add(classify(&object), 1);
+        }
+
+        census
+    }
+
+    pub fn add(&mut self, code: String, count: usize) {
+        *self.counts.entry(code).or_insert(0) += count;
+    }
+
+    pub fn merge(&mut self, other: &Census) {
+        for (code, count) in &other.counts {
+            self.add(code.clone(), *count);
+        }
+    }
+
+    pub fn counts(&self) -> &BTreeMap<String, usize> {
+        &self.counts
+    }
+
+    pub fn uncommon(&self) -> impl Iterator<Item = &str> {
+        self.counts
+            .keys()
+            .map(|code| code.as_str())
+            .filter(|code| !is_common(code))
+    }
+}
+
+/// How a pattern repeats: from generation `start` on, it comes back every
+/// `period` generations, moved by `displacement`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Periodicity {
+    pub start: usize,
+    pub period: usize,
+    pub displacement: Coord,
+}
+
+fn gcd(a: common::Int, b: common::Int) -> common::Int {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Periodicity {
+    /// Speed of a spaceship in the usual notation, like `c/4 diagonal`, or
+    /// `(2,1)c/6` for oblique ones
+    pub fn speed(&self) -> Option<String> {
+        let Coord(dx, dy) = self.displacement;
+        let period = self.period as common::Int;
+
+        if dx == 0 && dy == 0 {
+            return None;
+        }
+
+        let (adx, ady) = (dx.abs(), dy.abs());
+
+        if adx != ady && adx != 0 && ady != 0 {
+            return Some(format!("({},{})c/{}", adx, ady, period));
+        }
+
+        let distance = adx.max(ady);
+        let divisor = gcd(distance, period);
+        let (distance, period) = (distance / divisor, period / divisor);
+
+        let mut speed = match distance {
+            1 => "c".to_string(),
+            distance => format!("{}c", distance),
+        };
+
+        if period != 1 {
+            speed += &format!("/{}", period);
+        }
+
+        let direction = if adx == ady { "diagonal" } else { "orthogonal" };
+
+        Some(format!("{} {}", speed, direction))
+    }
+}
+
+/// Evolves the world until it repeats an earlier generation, up to
+/// translation. Only live cells are compared, so the decaying cells of
+/// Generations rules are ignored
+pub fn find_periodicity(world: &mut World, max_generations: usize) -> Option<Periodicity> {
+    let mut seen = HashMap::<Coords, (usize, Coord)>::new();
+    let start = world.gen();
+
+    loop {
+        let (phase, offset) = normalize(world.cells());
+
+        if let Some(&(generation, first)) = seen.get(&phase) {
+            return Some(Periodicity {
+                start: generation,
+                period: world.gen() - generation,
+                displacement: Coord(offset.0 - first.0, offset.1 - first.1),
+            });
+        }
+
+        seen.insert(phase, (world.gen(), offset));
+
+        if world.gen() - start >= max_generations || world.evolve().is_err() {
+            return None;
+        }
+    }
+}
+
+/// Splits cells sorted by `Coord` in groups of cells close to each other,
+/// keeping apart the touching parts of a group that evolve independently
+pub fn separate(cells: &[Coord]) -> Vec<Coords> {
+    cluster(cells, OBJECT_DISTANCE)
+        .into_iter()
+        .flat_map(|group| {
+            let parts = cluster(&group, 1);
+
+            if parts.len() > 1 && independent(&group, &parts) {
+                parts
+            } else {
+                vec![group]
+            }
+        })
+        .collect()
+}
+
+fn cluster(cells: &[Coord], distance: common::Int) -> Vec<Coords> {
+    let mut parents = (0..cells.len()).collect::<Vec<_>>();
+
+    fn root(parents: &mut [usize], i: usize) -> usize {
+        let mut i = i;
+
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+
+        i
+    }
+
+    for (i, c) in cells.iter().enumerate() {
+        for dx in -distance..=distance {
+            for dy in -distance..=distance {
+                if let Ok(j) = cells.binary_search(&Coord(c.0 + dx, c.1 + dy)) {
+                    let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                    parents[a.max(b)] = a.min(b);
+                }
+            }
+        }
+    }
+
+    let mut objects = BTreeMap::<usize, Coords>::new();
+
+    for (i, c) in cells.iter().enumerate() {
+        let r = root(&mut parents, i);
+        objects.entry(r).or_default().push(*c);
+    }
+
+    objects.into_values().collect()
+}
+
+fn world_of(cells: &[Coord]) -> World {
+    let mut world = World::new();
+    world.action(|world| {
+        for c in cells {
+            world.make_alive(*c);
+        }
+    });
+    world
+}
+
+/// Whether evolving the parts of a group on their own gives the same result
+/// as evolving the whole group
+fn independent(group: &[Coord], parts: &[Coords]) -> bool {
+    let mut whole = world_of(group);
+    let mut parts = parts.iter().map(|part| world_of(part)).collect::<Vec<_>>();
+
+    for _ in 0..INDEPENDENCE_GENERATIONS {
+        if whole.evolve().is_err() {
+            return false;
+        }
+
+        let mut cells = Coords::new();
+
+        for part in &mut parts {
+            if part.evolve().is_err() {
+                return false;
+            }
+
+            cells.extend_from_slice(part.cells());
+        }
+
+        cells.sort_unstable();
+
+        if cells != whole.cells() {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Gives the apgcode of an object, evolving it in isolation to find its period
+pub fn classify(object: &[Coord]) -> String {
+    let mut world = world_of(object);
+
+    let (first, first_offset) = normalize(world.cells());
+    let mut phases = vec![first.clone()];
+
+    for period in 1..=MAX_PERIOD {
+        if world.evolve().is_err() || world.population_size() == 0 {
+            break;
+        }
+
+        let (phase, offset) = normalize(world.cells());
+
+        if phase == first {
+            let prefix = if offset != first_offset {
+                "xq"
+            } else if period == 1 {
+                "xs"
+            } else {
+                "xp"
+            };
+
+            let size = if period == 1 { first.len() } else { period };
+
+            let code = best(phases.iter().map(|phase| canonical(phase)));
+
+            return format!("{}{}_{}", prefix, size, code);
+        }
+
+        phases.push(phase);
+    }
+
+    PATHOLOGICAL.to_string()
+}
+
+/// Moves cells so that their bounding box starts at the origin, returning
+/// them sorted and the offset that was removed
+fn normalize(cells: &[Coord]) -> (Coords, Coord) {
+    let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+    let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+
+    let mut normalized = cells
+        .iter()
+        .map(|c| Coord(c.0 - min_x, c.1 - min_y))
+        .collect::<Coords>();
+
+    normalized.sort_unstable();
+
+    (normalized, Coord(min_x, min_y))
+}
+
+/// Shorter codes are better, with ties broken lexicographically
+fn best<I: Iterator<Item = String>>(codes: I) -> String {
+    codes
+        .min_by(|a, b| (a.len(), a).cmp(&(b.len(), b)))
+        .unwrap_or_default()
+}
+
+/// The best extended Wechsler representation among all eight orientations
+fn canonical(cells: &[Coord]) -> String {
+    let orientations: [fn(&Coord) -> Coord; 8] = [
+        |c| Coord(c.0, c.1),
+        |c| Coord(-c.0, c.1),
+        |c| Coord(c.0, -c.1),
+        |c| Coord(-c.0, -c.1),
+        |c| Coord(c.1, c.0),
+        |c| Coord(-c.1, c.0),
+        |c| Coord(c.1, -c.0),
+        |c| Coord(-c.1, -c.0),
+    ];
+
+    best(orientations.iter().map(|orientation| {
+        let oriented = cells.iter().map(orientation).collect::<Coords>();
+        wechsler(&normalize(&oriented).0)
+    }))
+}
+
+/// Extended Wechsler format, as used by apgsearch and Catagolue
+fn wechsler(cells: &[Coord]) -> String {
+    const CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+
+    let width = cells.iter().map(|c| c.0).max().map_or(0, |x| x + 1) as usize;
+    let height = cells.iter().map(|c| c.1).max().map_or(0, |y| y + 1) as usize;
+
+    let mut grid = vec![false; width * height];
+
+    for c in cells {
+        grid[c.1 as usize * width + c.0 as usize] = true;
+    }
+
+    let mut code = String::new();
+
+    let push_zeroes = |code: &mut String, mut zeroes: usize| {
+        while zeroes > 0 {
+            let n = zeroes.min(39);
+
+            match n {
+                1 => code.push('0'),
+                2 => code.push('w'),
+                3 => code.push('x'),
+                _ => {
+                    code.push('y');
+                    code.push(CHARS[n - 4] as char);
+                }
+            }
+
+            zeroes -= n;
+        }
+    };
+
+    for strip in 0..height.div_ceil(5) {
+        if strip != 0 {
+            code.push('z');
+        }
+
+        let mut zeroes = 0;
+
+        for x in 0..width {
+            let value = (0..5)
+                .map(|row| strip * 5 + row)
+                .filter(|y| *y < height && grid[y * width + x])
+                .fold(0, |value, y| value | (1 << (y - strip * 5)));
+
+            if value == 0 {
+                zeroes += 1;
+            } else {
+                push_zeroes(&mut code, zeroes);
+                zeroes = 0;
+                code.push(CHARS[value] as char);
+            }
+        }
+    }
+
+    code
+}
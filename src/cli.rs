@@ -0,0 +1,1318 @@
+use crate::census;
+use crate::display::{Renderer, Step, TerminalDisplay, WorldDisplay};
+use crate::history::History;
+use crate::macrocell;
+use crate::plaintext::{self, PlaintextWriter};
+use crate::rle::{self, LifePlaceMaker, LreWriter};
+use crate::rule::{ParseRuleError, Rule};
+use crate::scene;
+use crate::search::SoupSearch;
+use crate::utils::WorldLifePlaceMaker;
+use crate::world::{Coord, Coords, OverflowError, World};
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn parse(arguments: &[&str]) -> Result<Command, CliError> {
+        parse_args(&args(arguments))
+    }
+
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("gameoflife-cli-{}", name));
+        fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    const GLIDER: &str = "#N Glider\n#O Richard K. Guy\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n";
+
+    #[test]
+    fn parse_run() {
+        assert_eq!(
+            parse(&["run", "glider.rle", "--gens", "10", "--renderer", "braille"]).unwrap(),
+            Command::Run(RunOptions {
+                file: "glider.rle".to_string(),
+                generations: Some(10),
+                rule: None,
+                renderer: Renderer::Braille,
+                history: None,
+            })
+        );
+
+        // a file alone runs it
+        let command = parse(&["glider.rle", "--rule=B36/S23"]).unwrap();
+        match command {
+            Command::Run(options) => assert_eq!(options.rule, Some("B36/S23".to_string())),
+            command => panic!("{:?}", command),
+        }
+
+        let command = parse(&["run-scene", "guns.toml", "--gens", "5"]).unwrap();
+        match command {
+            Command::RunScene(options) => {
+                assert_eq!(options.file, "guns.toml");
+                assert_eq!(options.generations, Some(5));
+            }
+            command => panic!("{:?}", command),
+        }
+    }
+
+    #[test]
+    fn parse_other_commands() {
+        assert_eq!(
+            parse(&["advance", "gun.rle", "--gens", "10000", "-o", "out.rle"]).unwrap(),
+            Command::Advance {
+                file: "gun.rle".to_string(),
+                generations: 10000,
+                rule: None,
+                output: Some("out.rle".to_string())
+            }
+        );
+        assert_eq!(
+            parse(&["info", "a.rle"]).unwrap(),
+            Command::Info {
+                file: "a.rle".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&["convert", "a.rle", "a.cells"]).unwrap(),
+            Command::Convert {
+                input: "a.rle".to_string(),
+                output: "a.cells".to_string()
+            }
+        );
+        assert_eq!(
+            parse(&["analyze", "a.rle", "--gens", "50"]).unwrap(),
+            Command::Analyze {
+                file: "a.rle".to_string(),
+                generations: 50,
+                rule: None
+            }
+        );
+        assert_eq!(
+            parse(&["search", "out", "--soups", "5", "--seed", "3"]).unwrap(),
+            Command::Search {
+                dir: "out".to_string(),
+                soups: Some(5),
+                seed: 3
+            }
+        );
+        assert_eq!(parse(&["info", "--help"]).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn usage_errors() {
+        let usage = |arguments: &[&str]| match parse(arguments) {
+            Err(CliError::Usage(message)) => message,
+            result => panic!("{:?}", result),
+        };
+
+        assert_eq!(usage(&["run"]), "run expects a file");
+        assert_eq!(usage(&["advance", "a"]), "advance expects --gens");
+        assert_eq!(usage(&["info", "a", "b"]), "unexpected argument 'b'");
+        assert_eq!(usage(&["run", "a", "--gens"]), "--gens expects a value");
+        assert_eq!(
+            usage(&["run", "a", "--gens", "many"]),
+            "invalid value 'many' for --gens"
+        );
+        assert_eq!(
+            usage(&["info", "a", "--gens", "1"]),
+            "unknown option --gens"
+        );
+        assert_eq!(
+            usage(&["run", "a", "--renderer", "3d"]),
+            "invalid value '3d' for --renderer"
+        );
+        assert_eq!(usage(&["frobnicate", "a"]), "unknown command 'frobnicate'");
+        assert_eq!(usage(&[]), "missing command");
+
+        assert_eq!(CliError::Usage(String::new()).exit_code(), 2);
+    }
+
+    #[test]
+    fn info_of_a_pattern() {
+        let file = temp_file("info.rle", GLIDER);
+        let mut out = Vec::new();
+
+        info(&file, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "name: Glider\nauthor: Richard K. Guy\nrule: B3/S23\nsize: 3x3\npopulation: 5\n"
+        );
+    }
+
+    #[test]
+    fn convert_between_formats() {
+        let input = temp_file("convert.rle", GLIDER);
+        let output = temp_file("convert.cells", "");
+
+        convert(&input, &output).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "!Name: Glider\n!Richard K. Guy\n.O\n..O\nOOO\n"
+        );
+
+        let back = temp_file("convert-back.rle", "");
+        convert(&output, &back).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&back).unwrap(),
+            "#N Glider\n#C Richard K. Guy\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+    }
+
+    #[test]
+    fn advance_a_glider() {
+        let file = temp_file("advance.rle", GLIDER);
+        let output = temp_file("advance-out.rle", "");
+
+        advance(&file, 8, None, Some(&output), &mut Vec::new()).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "#CXRLE Pos=2,2 Gen=8\n#N Glider\n#C Richard K. Guy\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+
+        // advancing the output again keeps counting from its position and
+        // generation
+        let mut out = Vec::new();
+        advance(&output, 4, None, None, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("#CXRLE Pos=3,3 Gen=12\n"));
+
+        let mut out = Vec::new();
+        advance(&file, 0, None, None, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .starts_with("#CXRLE Pos=0,0 Gen=0\n"));
+    }
+
+    #[test]
+    fn read_compressed_patterns() {
+        let mut gzip = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(GLIDER.as_bytes()).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let zstd = zstd::encode_all(GLIDER.as_bytes(), 0).unwrap();
+
+        for (name, content) in [("glider.rle.gz", gzip), ("glider.rle.zst", zstd)] {
+            let path = std::env::temp_dir().join(format!("gameoflife-cli-{}", name));
+            fs::write(&path, content).unwrap();
+
+            let mut out = Vec::new();
+            info(path.to_str().unwrap(), &mut out).unwrap();
+            assert!(String::from_utf8(out)
+                .unwrap()
+                .starts_with("name: Glider\n"));
+        }
+    }
+
+    #[test]
+    fn read_large_patterns_while_parsing() {
+        let rows = VALIDATED_RLE_SIZE as usize;
+        let content = format!("x = 1, y = {}\n{}o!", rows, "o$".repeat(rows / 2));
+        let file = temp_file("column.rle", &content);
+
+        let mut out = Vec::new();
+        info(&file, &mut out).unwrap();
+        assert!(String::from_utf8(out)
+            .unwrap()
+            .ends_with(&format!("population: {}\n", rows / 2 + 1)));
+
+        let file = temp_file("bad-column.rle", &content.replace('!', "z"));
+        let error = info(&file, &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().ends_with("line 2: unexpected 'z'"));
+    }
+
+    #[test]
+    fn detect_formats() {
+        assert_eq!(Format::of("a.cells.gz", b".O\n"), Format::Plaintext);
+        assert_eq!(Format::of("a.mc.zst", b""), Format::Macrocell);
+        assert_eq!(Format::of("a.rle", b"!"), Format::Rle);
+        assert_eq!(Format::of("-", GLIDER.as_bytes()), Format::Rle);
+        assert_eq!(Format::of("-", b"[M2] (golly 4.2)\n"), Format::Macrocell);
+        assert_eq!(Format::of("-", b"!Name: Glider\n"), Format::Plaintext);
+
+        let file = temp_file("glider.mc", "[M2]\n#R B3/S23\n.*$..*$***$\n4 0 0 0 1\n");
+        let mut out = Vec::new();
+        info(&file, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "rule: B3/S23\nsize: 3x3\npopulation: 5\n"
+        );
+    }
+
+    #[test]
+    fn load_a_scene() {
+        temp_file("scene-glider.rle", GLIDER);
+
+        let scene = temp_file(
+            "scene.toml",
+            "[[pattern]]
+            file = \"gameoflife-cli-scene-glider.rle\"
+
+            [[pattern]]
+            file = \"gameoflife-cli-scene-glider.rle\"
+            position = [10, 0]
+            flip = \"horizontal\"
+            phase = 4",
+        );
+
+        let world = load_scene(&scene, None).unwrap();
+
+        let mut expected = vec![
+            Coord(1, 0),
+            Coord(2, 1),
+            Coord(0, 2),
+            Coord(1, 2),
+            Coord(2, 2),
+            // flipped at [10, 0], then moved back and down by the phase
+            Coord(10, 1),
+            Coord(9, 2),
+            Coord(9, 3),
+            Coord(10, 3),
+            Coord(11, 3),
+        ];
+        expected.sort();
+        assert_eq!(world.cells(), &expected[..]);
+        assert_eq!(world.rule().to_string(), "B3/S23");
+
+        let scene = temp_file("bad-scene.toml", "[[pattern]]\nphase = 1\n");
+        let error = load_scene(&scene, None).err().unwrap();
+        assert!(error
+            .to_string()
+            .ends_with("bad-scene.toml: pattern 1: missing file"));
+    }
+
+    #[test]
+    fn scene_patterns_are_transformed_before_their_phase() {
+        // the hexagonal neighboorhood doesn't look the same once rotated
+        let rule = "B2/S34H";
+        temp_file("phase-hex.rle", "x = 3, y = 2, rule = B2/S34H\n3o$o!\n");
+
+        let scene = temp_file(
+            "phase-scene.toml",
+            "[[pattern]]
+            file = \"gameoflife-cli-phase-hex.rle\"
+            position = [5, -3]
+            rotation = 90
+            phase = 3",
+        );
+
+        let world = load_scene(&scene, None).unwrap();
+
+        let rotated = scene::Transform {
+            rotation: 90,
+            flip: None,
+        }
+        .apply(&[Coord(0, 0), Coord(1, 0), Coord(2, 0), Coord(0, 1)]);
+
+        let mut expected = World::with_rule(rule.parse().unwrap());
+        expected.action(|world| {
+            world.extend_alive(rotated.iter().map(|c| Coord(c.0 + 5, c.1 - 3)));
+        });
+
+        for _ in 0..3 {
+            expected.evolve().unwrap();
+        }
+
+        assert!(!expected.cells().is_empty());
+        assert_eq!(world.cells(), expected.cells());
+        assert_eq!(world.rule().to_string(), rule);
+
+        // on a bounded grid, the pattern wraps around the edges near its
+        // position
+        let scene = temp_file(
+            "phase-torus-scene.toml",
+            "rule = \"B3/S23:T6,6\"
+            [[pattern]]
+            file = \"gameoflife-cli-phase-glider.cells\"
+            phase = 4",
+        );
+        temp_file("phase-glider.cells", ".O\n..O\nOOO\n");
+
+        let world = load_scene(&scene, None).unwrap();
+
+        let mut expected = [
+            Coord(2, 1),
+            Coord(-3, 2),
+            Coord(1, -3),
+            Coord(2, -3),
+            Coord(-3, -3),
+        ];
+        expected.sort();
+        assert_eq!(world.cells(), &expected[..]);
+    }
+
+    #[test]
+    fn scene_patterns_must_agree_on_the_rule() {
+        temp_file("rules-glider.rle", GLIDER);
+        temp_file("rules-highlife.rle", "x = 1, y = 1, rule = B36/S23\no!\n");
+        temp_file("rules-plain.cells", "O\n");
+
+        let scene = temp_file(
+            "rules-scene.toml",
+            "[[pattern]]
+            file = \"gameoflife-cli-rules-glider.rle\"
+
+            [[pattern]]
+            file = \"gameoflife-cli-rules-plain.cells\"
+
+            [[pattern]]
+            file = \"gameoflife-cli-rules-highlife.rle\"
+            position = [10, 0]",
+        );
+
+        let error = load_scene(&scene, None).err().unwrap();
+        assert!(error.to_string().ends_with(
+            "rules-scene.toml: pattern 3: gameoflife-cli-rules-highlife.rle has rule B36/S23, \
+             but the scene has B3/S23"
+        ));
+
+        // unless the rule is given
+        let world = load_scene(&scene, Some("B36/S23")).unwrap();
+        assert_eq!(world.rule().to_string(), "B36/S23");
+        assert_eq!(world.cells().len(), 7);
+
+        let scene = temp_file(
+            "rules-highlife-scene.toml",
+            "rule = \"B36/S23\"
+            [[pattern]]
+            file = \"gameoflife-cli-rules-highlife.rle\"
+
+            [[pattern]]
+            file = \"gameoflife-cli-rules-glider.rle\"
+            position = [10, 0]",
+        );
+
+        let error = load_scene(&scene, None).err().unwrap();
+        assert!(error.to_string().ends_with(
+            "pattern 2: gameoflife-cli-rules-glider.rle has rule B3/S23, but the scene has B36/S23"
+        ));
+    }
+
+    #[test]
+    fn advance_fails_on_infinite_patterns() {
+        let file = temp_file("advance-b0.rle", GLIDER);
+
+        let error = advance(&file, 1, Some("B0123478/S34678"), None, &mut Vec::new()).unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "generation 1 has infinitely many live cells, as the rule has B0"
+        );
+    }
+
+    #[test]
+    fn analyze_a_spaceship() {
+        let file = temp_file("analyze.rle", GLIDER);
+        let mut out = Vec::new();
+
+        analyze(&file, 100, None, &mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "spaceship with period 4, moving (1, 1): c/4 diagonal\n"
+        );
+    }
+
+    #[test]
+    fn readable_errors() {
+        let error = info("/no/such/file.rle", &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().starts_with("/no/such/file.rle: "));
+        assert_eq!(error.exit_code(), 1);
+
+        let file = temp_file("bad-rule.rle", "x = 1, y = 1, rule = B9/S\no!");
+        let error = info(&file, &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().starts_with("invalid rule"));
+
+        let file = temp_file("bad.cells", "..x\n");
+        let error = info(&file, &mut Vec::new()).unwrap_err();
+        assert!(error
+            .to_string()
+            .ends_with("bad.cells: line 1: unexpected 'x', cells must be '.' or 'O'"));
+    }
+}
+
+pub const USAGE: &str = "\
+Usage: gameoflife <command> [options]
+
+Commands:
+  run <file>                Shows the pattern evolving on the terminal
+      --gens <n>            Stops after n generations
+      --rule <rule>         Uses another rule than the one of the file
+      --renderer <name>     ascii (default) or braille, for 2x4 cells per character
+      --history <file>      Writes population statistics as CSV, or JSON if the
+                            file name ends with .json
+  run-scene <file>          Shows the patterns of a TOML scene file evolving, with
+                            the options of run
+  advance <file>            Writes the pattern at a later generation as RLE,
+                            without showing it
+      --gens <n>            Generations to advance
+      --rule <rule>         Uses another rule than the one of the file
+      -o, --output <file>   Writes to the file instead of the standard output
+  info <file>               Shows the size, population and metadata of a pattern
+  convert <input> <output>  Converts between RLE (.rle) and plaintext (.cells)
+  analyze <file>            Finds the period and speed of a pattern
+      --gens <n>            Gives up after n generations (default: 1000)
+      --rule <rule>         Uses another rule than the one of the file
+  search <dir>              Runs random soups, writing the interesting ones to dir
+      --soups <n>           Number of soups (default: 1000)
+      --seed <n>            Seed of the random soups (default: 0)
+
+  Patterns can be RLE, plaintext (.cells) or macrocell (.mc) files, compressed
+  with gzip or zstd or not, and - reads one from the standard input
+  gameoflife <file> is the same as gameoflife run <file>
+  -h, --help                Shows this message
+";
+
+/// Generations `analyze` looks for a period in, by default
+const ANALYZE_GENERATIONS: usize = 1000;
+
+/// The viewer can rewind up to REWIND_INTERVAL * REWIND_SNAPSHOTS
+/// generations, simulating again up to REWIND_INTERVAL of them at each step
+const REWIND_INTERVAL: usize = 32;
+const REWIND_SNAPSHOTS: usize = 64;
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run(RunOptions),
+    /// Runs several patterns placed by a scene file
+    RunScene(RunOptions),
+    Advance {
+        file: String,
+        generations: usize,
+        rule: Option<String>,
+        output: Option<String>,
+    },
+    Info {
+        file: String,
+    },
+    Convert {
+        input: String,
+        output: String,
+    },
+    Analyze {
+        file: String,
+        generations: usize,
+        rule: Option<String>,
+    },
+    Search {
+        dir: String,
+        soups: Option<usize>,
+        seed: u64,
+    },
+    Help,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunOptions {
+    pub file: String,
+    pub generations: Option<usize>,
+    pub rule: Option<String>,
+    pub renderer: Renderer,
+    pub history: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum CliError {
+    /// The command line is wrong
+    Usage(String),
+    Io {
+        path: String,
+        error: io::Error,
+    },
+    /// A pattern file that can't be parsed
+    Pattern {
+        path: String,
+        reason: String,
+    },
+    Rule(ParseRuleError),
+    Overflow(OverflowError),
+    /// Rules with B0 make every cell of the plane alive every other generation
+    InfinitePattern {
+        generation: usize,
+    },
+}
+
+impl CliError {
+    /// 2 for usage errors, 1 for any other error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 2,
+            _ => 1,
+        }
+    }
+
+    fn io(path: &str) -> impl FnOnce(io::Error) -> CliError + '_ {
+        move |error| CliError::Io {
+            path: path.to_string(),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => {
+                write!(f, "{}\nRun 'gameoflife --help' for usage", message)
+            }
+            CliError::Io { path, error } => write!(f, "{}: {}", path, error),
+            CliError::Pattern { path, reason } => write!(f, "invalid pattern {}: {}", path, reason),
+            CliError::Rule(error) => write!(f, "{}", error),
+            CliError::Overflow(error) => write!(f, "{}", error),
+            CliError::InfinitePattern { generation } => write!(
+                f,
+                "generation {} has infinitely many live cells, as the rule has B0",
+                generation
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+impl From<OverflowError> for CliError {
+    fn from(error: OverflowError) -> Self {
+        CliError::Overflow(error)
+    }
+}
+
+impl From<ParseRuleError> for CliError {
+    fn from(error: ParseRuleError) -> Self {
+        CliError::Rule(error)
+    }
+}
+
+/// Positional arguments and the values of the options of a command
+struct Arguments {
+    positional: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+impl Arguments {
+    /// Accepts `--name value` and `--name=value` for the given options
+    fn split(args: &[String], known: &[&str]) -> Result<Self, CliError> {
+        let mut arguments = Arguments {
+            positional: vec![],
+            options: vec![],
+        };
+
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            // the only short option
+            let arg = match arg.as_str() {
+                "-o" => "--output",
+                arg => arg,
+            };
+
+            if !arg.starts_with("--") {
+                arguments.positional.push(arg.to_string());
+                continue;
+            }
+
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (arg, None),
+            };
+
+            if !known.contains(&&name[2..]) {
+                return Err(CliError::Usage(format!("unknown option {}", name)));
+            }
+
+            let value = match value.or_else(|| args.next().cloned()) {
+                Some(value) => value,
+                None => return Err(CliError::Usage(format!("{} expects a value", name))),
+            };
+
+            arguments.options.push((name[2..].to_string(), value));
+        }
+
+        Ok(arguments)
+    }
+
+    /// Exactly `count` positional arguments, described as `names` when missing
+    fn positional(&self, command: &str, names: &str, count: usize) -> Result<&[String], CliError> {
+        if let Some(extra) = self.positional.get(count) {
+            return Err(CliError::Usage(format!("unexpected argument '{}'", extra)));
+        }
+
+        if self.positional.len() < count {
+            return Err(CliError::Usage(format!("{} expects {}", command, names)));
+        }
+
+        Ok(&self.positional)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| option == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn parsed_option<T: std::str::FromStr>(&self, name: &str) -> Result<Option<T>, CliError> {
+        match self.option(name) {
+            None => Ok(None),
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| CliError::Usage(format!("invalid value '{}' for --{}", value, name))),
+        }
+    }
+}
+
+/// Arguments without the program name
+pub fn parse_args(args: &[String]) -> Result<Command, CliError> {
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        return Ok(Command::Help);
+    }
+
+    if args.is_empty() {
+        return Err(CliError::Usage("missing command".to_string()));
+    }
+
+    let (command, rest) = match args[0].as_str() {
+        "run" | "run-scene" | "advance" | "info" | "convert" | "analyze" | "search" => {
+            (args[0].as_str(), &args[1..])
+        }
+        "help" => return Ok(Command::Help),
+        file if file.starts_with('-') || Path::new(file).extension().is_some() => ("run", args),
+        command => return Err(CliError::Usage(format!("unknown command '{}'", command))),
+    };
+
+    match command {
+        "run" | "run-scene" => {
+            let arguments = Arguments::split(rest, &["gens", "rule", "renderer", "history"])?;
+            let file = arguments.positional(command, "a file", 1)?[0].clone();
+
+            let renderer = match arguments.option("renderer") {
+                None | Some("ascii") => Renderer::Ascii,
+                Some("braille") => Renderer::Braille,
+                Some(renderer) => {
+                    return Err(CliError::Usage(format!(
+                        "invalid value '{}' for --renderer",
+                        renderer
+                    )))
+                }
+            };
+
+            let options = RunOptions {
+                file,
+                generations: arguments.parsed_option("gens")?,
+                rule: arguments.option("rule").map(String::from),
+                renderer,
+                history: arguments.option("history").map(String::from),
+            };
+
+            Ok(match command {
+                "run" => Command::Run(options),
+                _ => Command::RunScene(options),
+            })
+        }
+        "advance" => {
+            let arguments = Arguments::split(rest, &["gens", "rule", "output"])?;
+            let file = arguments.positional("advance", "a file", 1)?[0].clone();
+
+            let generations = match arguments.parsed_option("gens")? {
+                Some(generations) => generations,
+                None => return Err(CliError::Usage("advance expects --gens".to_string())),
+            };
+
+            Ok(Command::Advance {
+                file,
+                generations,
+                rule: arguments.option("rule").map(String::from),
+                output: arguments.option("output").map(String::from),
+            })
+        }
+        "info" => {
+            let arguments = Arguments::split(rest, &[])?;
+            let file = arguments.positional("info", "a file", 1)?[0].clone();
+
+            Ok(Command::Info { file })
+        }
+        "convert" => {
+            let arguments = Arguments::split(rest, &[])?;
+            let files = arguments.positional("convert", "an input and an output file", 2)?;
+
+            Ok(Command::Convert {
+                input: files[0].clone(),
+                output: files[1].clone(),
+            })
+        }
+        "analyze" => {
+            let arguments = Arguments::split(rest, &["gens", "rule"])?;
+            let file = arguments.positional("analyze", "a file", 1)?[0].clone();
+
+            Ok(Command::Analyze {
+                file,
+                generations: arguments
+                    .parsed_option("gens")?
+                    .unwrap_or(ANALYZE_GENERATIONS),
+                rule: arguments.option("rule").map(String::from),
+            })
+        }
+        _ => {
+            let arguments = Arguments::split(rest, &["soups", "seed"])?;
+            let dir = arguments.positional("search", "an output directory", 1)?[0].clone();
+
+            Ok(Command::Search {
+                dir,
+                soups: arguments.parsed_option("soups")?,
+                seed: arguments.parsed_option("seed")?.unwrap_or(0),
+            })
+        }
+    }
+}
+
+/// What a pattern file says besides its cells
+struct PatternInfo {
+    generation: usize,
+    width: usize,
+    height: usize,
+    rule: Option<String>,
+    name: Option<String>,
+    author: Option<String>,
+    comments: Vec<String>,
+}
+
+fn is_plaintext(path: &str) -> bool {
+    path.ends_with(".cells")
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Opens a file, or the standard input for `-`, decompressing it if it's
+/// gzip or zstd compressed, whatever its name
+fn open_input(path: &str) -> Result<Box<dyn BufRead>, CliError> {
+    let input: Box<dyn Read> = match path {
+        "-" => Box::new(io::stdin()),
+        path => Box::new(fs::File::open(path).map_err(CliError::io(path))?),
+    };
+
+    let mut input = io::BufReader::new(input);
+    let magic = input.fill_buf().map_err(CliError::io(path))?;
+
+    Ok(if magic.starts_with(GZIP_MAGIC) {
+        Box::new(io::BufReader::new(flate2::bufread::MultiGzDecoder::new(
+            input,
+        )))
+    } else if magic.starts_with(ZSTD_MAGIC) {
+        let decoder = zstd::Decoder::with_buffer(input).map_err(CliError::io(path))?;
+        Box::new(io::BufReader::new(decoder))
+    } else {
+        Box::new(input)
+    })
+}
+
+/// RLE files up to this size are also validated by the grammar, larger ones
+/// are parsed as they're read
+const VALIDATED_RLE_SIZE: u64 = 1 << 20;
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Rle,
+    Plaintext,
+    Macrocell,
+}
+
+impl Format {
+    /// By the extension of the file, ignoring the one of the compression,
+    /// or else by the first bytes, as for the standard input
+    fn of(path: &str, start: &[u8]) -> Format {
+        let path = path.trim_end_matches(".gz").trim_end_matches(".zst");
+
+        if is_plaintext(path) {
+            Format::Plaintext
+        } else if path.ends_with(".mc") || start.starts_with(b"[M2]") {
+            Format::Macrocell
+        } else if path.ends_with(".rle") || !matches!(start.first(), Some(b'!' | b'.' | b'O')) {
+            Format::Rle
+        } else {
+            Format::Plaintext
+        }
+    }
+}
+
+/// Loads an RLE, plaintext or macrocell pattern, with `rule` instead of the
+/// rule of the file if given
+fn load(path: &str, rule: Option<&str>) -> Result<(World, PatternInfo), CliError> {
+    let mut input = open_input(path)?;
+    let format = Format::of(path, input.fill_buf().map_err(CliError::io(path))?);
+
+    let mut start = vec![];
+    match format {
+        Format::Rle => (&mut input)
+            .take(VALIDATED_RLE_SIZE)
+            .read_to_end(&mut start),
+        _ => input.read_to_end(&mut start),
+    }
+    .map_err(CliError::io(path))?;
+
+    let (content, mut stream) = if start.len() as u64 == VALIDATED_RLE_SIZE {
+        (String::new(), Some(io::Cursor::new(start).chain(input)))
+    } else {
+        let content = String::from_utf8(start)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+            .map_err(CliError::io(path))?;
+        (content, None)
+    };
+
+    let mut world = World::new();
+    let mut parsed = Err(String::new());
+
+    world.action(|world| {
+        let mut placemaker = WorldLifePlaceMaker::new(Coord(0, 0), world);
+
+        parsed = match format {
+            Format::Plaintext => plaintext::parse(&content, &mut placemaker)
+                .map(|life| PatternInfo {
+                    generation: 0,
+                    width: life.x as usize,
+                    height: life.y as usize,
+                    rule: None,
+                    name: life.name,
+                    author: None,
+                    comments: life.comments,
+                })
+                .map_err(|error| error.to_string()),
+            Format::Macrocell => macrocell::parse(&content, &mut placemaker)
+                .map(|life| PatternInfo {
+                    generation: life.generation.unwrap_or(0),
+                    width: life.x as usize,
+                    height: life.y as usize,
+                    rule: life.rule,
+                    name: None,
+                    author: None,
+                    comments: life.comments,
+                })
+                .map_err(|error| error.to_string()),
+            Format::Rle => match &mut stream {
+                Some(stream) => {
+                    rle::parse_reader(stream, &mut placemaker).map_err(|error| error.to_string())
+                }
+                None => rle::parse(&content, &mut placemaker).map_err(|error| error.to_string()),
+            }
+            .map(|life| PatternInfo {
+                generation: life.generation.unwrap_or(0),
+                width: life.x as usize,
+                height: life.y as usize,
+                rule: life.rule,
+                name: life.name,
+                author: life.author,
+                comments: life.comments,
+            }),
+        };
+    });
+
+    let info = parsed.map_err(|reason| CliError::Pattern {
+        path: path.to_string(),
+        reason,
+    })?;
+
+    if let Some(rule) = rule.or(info.rule.as_deref()) {
+        world.set_rule(rule.parse::<Rule>()?);
+    }
+
+    world.set_gen(info.generation);
+
+    Ok((world, info))
+}
+
+/// Loads the patterns of a scene, each transformed, placed at its position
+/// and then evolved to its phase under the rule of the scene. Without a rule given or in the scene, the rule is
+/// the one of the first pattern. Patterns with a rule of their own must
+/// agree with it, unless the rule is given
+fn load_scene(path: &str, rule: Option<&str>) -> Result<World, CliError> {
+    let content = fs::read_to_string(path).map_err(CliError::io(path))?;
+    let scene = scene::parse(&content).map_err(|error| CliError::Pattern {
+        path: path.to_string(),
+        reason: error.to_string(),
+    })?;
+
+    let dir = Path::new(path).parent().unwrap_or_else(|| Path::new(""));
+
+    let mut scene_rule = match rule.or(scene.rule.as_deref()) {
+        Some(rule) => Some(rule.parse::<Rule>()?),
+        None => None,
+    };
+    let mut patterns = vec![];
+
+    for (i, pattern) in scene.patterns.iter().enumerate() {
+        let file = dir.join(&pattern.file);
+        let (world, info) = load(&file.to_string_lossy(), rule)?;
+
+        let scene_rule = match &scene_rule {
+            None => scene_rule.insert(world.rule().clone()),
+            Some(scene_rule) => {
+                if rule.is_none() && info.rule.is_some() && world.rule() != scene_rule {
+                    let reason = format!(
+                        "pattern {}: {} has rule {}, but the scene has {}",
+                        i + 1,
+                        pattern.file,
+                        world.rule(),
+                        scene_rule
+                    );
+
+                    return Err(CliError::Pattern {
+                        path: path.to_string(),
+                        reason,
+                    });
+                }
+
+                scene_rule
+            }
+        };
+
+        // dying cells are transformed along with the alive ones
+        let states = world
+            .cells()
+            .iter()
+            .map(|c| (*c, 1))
+            .chain(world.dying().iter().copied())
+            .collect::<Vec<_>>();
+        let cells = states.iter().map(|(c, _)| *c).collect::<Vec<_>>();
+        let cells = pattern.transform.apply(&cells);
+
+        // rules may not look the same once rotated or flipped, and bounded
+        // grids wrap around the position, so the phase comes last
+        let mut placed = World::with_rule(scene_rule.clone());
+
+        placed.action(|world| {
+            let mut placemaker = WorldLifePlaceMaker::new(pattern.position, world);
+
+            for (c, (_, state)) in cells.into_iter().zip(states) {
+                placemaker.make_cell_state(c, state);
+            }
+        });
+
+        for _ in 0..pattern.phase {
+            placed.evolve()?;
+        }
+
+        if placed.is_inverted() {
+            return Err(CliError::InfinitePattern {
+                generation: placed.gen(),
+            });
+        }
+
+        patterns.push(placed);
+    }
+
+    let mut world = World::with_rule(scene_rule.unwrap_or_default());
+
+    world.action(|world| {
+        for placed in patterns {
+            world.extend_alive(placed.cells().iter().copied());
+
+            for (c, state) in placed.dying() {
+                world.make_dying(*c, *state);
+            }
+        }
+    });
+
+    Ok(world)
+}
+
+/// Parses the arguments (without the program name) and runs the command
+pub fn main(args: &[String]) -> Result<(), CliError> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    match parse_args(args)? {
+        Command::Run(options) => {
+            let (world, _) = load(&options.file, options.rule.as_deref())?;
+            run(world, &options)
+        }
+        Command::RunScene(options) => {
+            let world = load_scene(&options.file, options.rule.as_deref())?;
+            run(world, &options)
+        }
+        Command::Advance {
+            file,
+            generations,
+            rule,
+            output,
+        } => advance(
+            &file,
+            generations,
+            rule.as_deref(),
+            output.as_deref(),
+            &mut out,
+        ),
+        Command::Info { file } => info(&file, &mut out),
+        Command::Convert { input, output } => convert(&input, &output),
+        Command::Analyze {
+            file,
+            generations,
+            rule,
+        } => analyze(&file, generations, rule.as_deref(), &mut out),
+        Command::Search { dir, soups, seed } => search(&dir, soups, seed, &mut out),
+        Command::Help => write!(out, "{}", USAGE).map_err(CliError::io("stdout")),
+    }
+}
+
+fn run(mut world: World, options: &RunOptions) -> Result<(), CliError> {
+    if options.history.is_some() {
+        world.start_recording();
+    }
+
+    let mut display = TerminalDisplay::new();
+    display.set_renderer(options.renderer);
+
+    let mut window = display.best_window(0, 0);
+    let mut cells = Coords::new();
+    let mut past = History::new(REWIND_INTERVAL, REWIND_SNAPSHOTS);
+    let last = options
+        .generations
+        .map(|generations| world.gen() + generations);
+
+    while last.is_none_or(|last| world.gen() < last) {
+        cells.clear();
+        world.live_cells(&window, &mut cells);
+        display.display(&cells, &mut window, &world);
+
+        match display.step() {
+            Step::Forward => {
+                past.record(&world);
+
+                if let Err(error) = world.evolve() {
+                    eprintln!("{}", error);
+                    break;
+                }
+            }
+            Step::Backward => {
+                past.rewind(&mut world);
+            }
+            Step::Replay => {
+                past.replay(&mut world);
+            }
+            Step::Toggle(c) => {
+                world.toggle_cell(c);
+                past.record_edit(&world);
+            }
+            Step::Stay => {}
+        }
+
+        display.update_window(&mut window, &world);
+    }
+
+    if let (Some(path), Some(recorder)) = (&options.history, world.recorder()) {
+        let mut file = fs::File::create(path).map_err(CliError::io(path))?;
+
+        if path.ends_with(".json") {
+            recorder.write_json(&mut file)
+        } else {
+            recorder.write_csv(&mut file)
+        }
+        .map_err(CliError::io(path))?;
+    }
+
+    Ok(())
+}
+
+fn info(path: &str, out: &mut dyn io::Write) -> Result<(), CliError> {
+    let (world, pattern) = load(path, None)?;
+
+    let mut lines = vec![];
+
+    if let Some(name) = &pattern.name {
+        lines.push(format!("name: {}", name));
+    }
+
+    if let Some(author) = &pattern.author {
+        lines.push(format!("author: {}", author));
+    }
+
+    lines.push(format!("rule: {}", world.rule()));
+
+    if world.gen() != 0 {
+        lines.push(format!("generation: {}", world.gen()));
+    }
+
+    lines.push(format!("size: {}x{}", pattern.width, pattern.height));
+    lines.push(format!("population: {}", world.population_size()));
+
+    for comment in &pattern.comments {
+        lines.push(format!("comment: {}", comment));
+    }
+
+    for line in lines {
+        writeln!(out, "{}", line).map_err(CliError::io("stdout"))?;
+    }
+
+    Ok(())
+}
+
+/// Writes a pattern as plaintext or as RLE, with its metadata. Authors are
+/// kept as comments, and the generation is only written to RLE
+fn write_pattern(
+    world: &World,
+    pattern: &PatternInfo,
+    generation: Option<usize>,
+    plaintext: bool,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    let comments = pattern
+        .author
+        .iter()
+        .chain(pattern.comments.iter())
+        .collect::<Vec<_>>();
+
+    if plaintext {
+        let mut writer = PlaintextWriter::new();
+
+        if let Some(name) = &pattern.name {
+            writer.name(name);
+        }
+
+        for comment in comments {
+            writer.comment(comment);
+        }
+
+        return writer.write(world.cells(), out);
+    }
+
+    let mut writer = LreWriter::new();
+    writer.rule(&world.rule().to_string());
+
+    if let Some(generation) = generation {
+        writer.generation(generation);
+    }
+
+    if let Some(name) = &pattern.name {
+        writer.name(name);
+    }
+
+    for comment in comments {
+        writer.comment(comment);
+    }
+
+    let cells = world
+        .cells()
+        .iter()
+        .map(|c| (*c, 1))
+        .chain(world.dying().iter().copied())
+        .collect::<Vec<_>>();
+
+    writer.write_states(&cells, out)
+}
+
+/// Evolves the pattern without showing it, writing it as RLE to `output`, or
+/// to `out` if not given
+fn advance(
+    path: &str,
+    generations: usize,
+    rule: Option<&str>,
+    output: Option<&str>,
+    out: &mut dyn io::Write,
+) -> Result<(), CliError> {
+    let (mut world, pattern) = load(path, rule)?;
+
+    for _ in 0..generations {
+        world.evolve()?;
+    }
+
+    if world.is_inverted() {
+        return Err(CliError::InfinitePattern {
+            generation: world.gen(),
+        });
+    }
+
+    match output {
+        Some(output) => {
+            let mut file = fs::File::create(output).map_err(CliError::io(output))?;
+            write_pattern(&world, &pattern, Some(world.gen()), false, &mut file)
+                .map_err(CliError::io(output))
+        }
+        None => write_pattern(&world, &pattern, Some(world.gen()), false, out)
+            .map_err(CliError::io("stdout")),
+    }
+}
+
+/// Writes the input pattern in the format of the output file name: plaintext
+/// for `.cells`, RLE otherwise
+fn convert(input: &str, output: &str) -> Result<(), CliError> {
+    let (world, pattern) = load(input, None)?;
+
+    let mut file = fs::File::create(output).map_err(CliError::io(output))?;
+
+    write_pattern(&world, &pattern, None, is_plaintext(output), &mut file)
+        .map_err(CliError::io(output))
+}
+
+fn analyze(
+    path: &str,
+    generations: usize,
+    rule: Option<&str>,
+    out: &mut dyn io::Write,
+) -> Result<(), CliError> {
+    let (mut world, _) = load(path, rule)?;
+
+    let report = match census::find_periodicity(&mut world, generations) {
+        None => format!("no period found in {} generations", generations),
+        Some(_) if world.live_population() == Some(0) => {
+            format!("dies out at generation {}", world.gen())
+        }
+        Some(periodicity) => {
+            let settled = match periodicity.start {
+                0 => String::new(),
+                start => format!(" from generation {}", start),
+            };
+
+            match periodicity.speed() {
+                Some(speed) => format!(
+                    "spaceship with period {}{}, moving ({}, {}): {}",
+                    periodicity.period,
+                    settled,
+                    periodicity.displacement.0,
+                    periodicity.displacement.1,
+                    speed
+                ),
+                None if periodicity.period == 1 => format!("still life{}", settled),
+                None => format!("oscillator with period {}{}", periodicity.period, settled),
+            }
+        }
+    };
+
+    writeln!(out, "{}", report).map_err(CliError::io("stdout"))
+}
+
+fn search(
+    dir: &str,
+    soups: Option<usize>,
+    seed: u64,
+    out: &mut dyn io::Write,
+) -> Result<(), CliError> {
+    let mut search = SoupSearch::new(seed);
+
+    if let Some(soups) = soups {
+        search.soups = soups;
+    }
+
+    let report = search.run();
+
+    writeln!(
+        out,
+        "Searched {} soups, {} interesting",
+        report.soups,
+        report.interesting.len()
+    )
+    .map_err(CliError::io("stdout"))?;
+
+    report.write(Path::new(dir)).map_err(CliError::io(dir))
+}
@@ -2,6 +2,110 @@ extern crate crossterm;
 
 use super::world;
 use crate::common;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Coord;
+
+    #[test]
+    fn braille_packs_two_by_four_cells() {
+        let window = world::Window::new(10, 20, 4, 8);
+        let cells = [Coord(10, 20), Coord(11, 23), Coord(13, 27)];
+
+        assert_eq!(
+            braille(&cells, &window),
+            vec![((0, 0), '\u{2881}'), ((1, 1), '\u{2880}')]
+        );
+    }
+
+    #[test]
+    fn population_of_live_cells() {
+        let mut world = world::World::with_rule("B013/S0124:T10,10".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+            world.make_alive(Coord(3, 3));
+        });
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+        assert_eq!(population(&world), "100");
+
+        world.set_rule("B013/S0124".parse().unwrap());
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+        assert_eq!(population(&world), "infinite");
+    }
+}
+
+/// Generations shown in the population sparkline of the status bar
+const SPARKLINE_WIDTH: usize = 40;
+
+/// Shades for the decaying states of Generations rules, from recently dead
+/// to almost dead
+const DECAY_SHADES: [char; 3] = ['▓', '▒', '░'];
+
+fn decay_shade(state: u8, states: u8) -> char {
+    let decaying_states = (states as usize).saturating_sub(2).max(1);
+    let index = (state as usize).saturating_sub(2) * DECAY_SHADES.len() / decaying_states;
+    DECAY_SHADES[index.min(DECAY_SHADES.len() - 1)]
+}
+
+/// How cells are drawn on the terminal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Renderer {
+    /// A character per cell
+    Ascii,
+    /// A braille character per 2x4 cells, showing a pattern 8 times as large
+    Braille,
+}
+
+/// Bits of the dots of a braille character, by column and row
+const BRAILLE_DOTS: [[u32; 4]; 2] = [[0x01, 0x02, 0x04, 0x40], [0x08, 0x10, 0x20, 0x80]];
+
+/// Braille characters for the cells of a window, by terminal position
+fn braille(cells: &[world::Coord], window: &world::Window) -> Vec<((u16, u16), char)> {
+    let mut chars = BTreeMap::<(u16, u16), u32>::new();
+
+    for c in cells {
+        let (x, y) = ((c.0 - window.x) as usize, (c.1 - window.y) as usize);
+        *chars.entry(((y / 4) as u16, (x / 2) as u16)).or_insert(0) |= BRAILLE_DOTS[x % 2][y % 4];
+    }
+
+    chars
+        .into_iter()
+        .map(|((row, column), dots)| {
+            let c = std::char::from_u32(0x2800 + dots).unwrap();
+            ((column, row), c)
+        })
+        .collect()
+}
+
+/// Live cells, which are infinitely many when the world is inverted on an
+/// unbounded grid
+fn population(world: &world::World) -> String {
+    match world.live_population() {
+        Some(population) => population.to_string(),
+        None => "infinite".to_string(),
+    }
+}
+
+/// What the keys pressed on the viewer ask the simulation to do next
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Step {
+    /// Evolves, dropping the generations that were rewound
+    Forward,
+    /// Goes back a generation
+    Backward,
+    /// Goes forward a generation that was rewound, with its edits
+    Replay,
+    /// Toggles a cell in the editor, staying at the same generation
+    Toggle(world::Coord),
+    /// Shows the same generation again, as when moving the editor's cursor
+    Stay,
+}
 
 pub trait WorldDisplay {
     fn display(&mut self, cells: &[world::Coord], window: &mut world::Window, world: &world::World);
@@ -11,6 +115,17 @@ pub struct TerminalDisplay<'stdout> {
     terminal: crossterm::terminal::Terminal<'stdout>,
     input: crossterm::input::TerminalInput<'stdout>,
     cursor: crossterm::cursor::TerminalCursor<'stdout>,
+    following: bool,
+    renderer: Renderer,
+    step: Step,
+    /// The cell under the cursor, while editing
+    cursor_cell: Option<world::Coord>,
+}
+
+impl<'stdout> Default for TerminalDisplay<'stdout> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'stdout> TerminalDisplay<'stdout> {
@@ -19,20 +134,90 @@ impl<'stdout> TerminalDisplay<'stdout> {
             terminal: crossterm::terminal::terminal(),
             input: crossterm::input::input(),
             cursor: crossterm::cursor::cursor(),
+            following: false,
+            renderer: Renderer::Ascii,
+            step: Step::Forward,
+            cursor_cell: None,
         }
     }
 
+    pub fn set_renderer(&mut self, renderer: Renderer) {
+        self.renderer = renderer;
+    }
+
     fn clear(&self) {
         let _ = self.terminal.clear(crossterm::terminal::ClearType::All);
     }
 
     pub fn best_window(&self, x: common::Int, y: common::Int) -> world::Window {
         let (w, h) = self.terminal.terminal_size();
-        world::Window::new(x, y, w as usize, h as usize)
+
+        match self.renderer {
+            Renderer::Ascii => world::Window::new(x, y, w as usize, h as usize),
+            Renderer::Braille => world::Window::new(x, y, w as usize * 2, h as usize * 4),
+        }
+    }
+
+    fn center(window: &mut world::Window, world: &world::World) {
+        if let Some((min, max)) = world.bounding_box() {
+            window.center_on(min, max);
+        }
+    }
+
+    /// What the last key read by `update_window` asks for: `u` rewinds, `r`
+    /// replays and, while editing, space toggles the cell under the cursor.
+    /// Most other keys evolve
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// Keys of the editor, which `e` turns on and off: `i`, `j`, `k` and `l`
+    /// move the cursor and space toggles the cell under it
+    fn edit(&mut self, c: char, window: &world::Window) -> bool {
+        let cursor = match (c, self.cursor_cell) {
+            ('e', None) => {
+                let center = world::Coord(
+                    window.x + (window.w / 2) as common::Int,
+                    window.y + (window.h / 2) as common::Int,
+                );
+                self.cursor_cell = Some(center);
+                self.step = Step::Stay;
+                return true;
+            }
+            ('e', Some(_)) => {
+                self.cursor_cell = None;
+                self.step = Step::Stay;
+                return true;
+            }
+            (_, None) => return false,
+            (_, Some(cursor)) => cursor,
+        };
+
+        let moved = match c {
+            'i' => world::Coord(cursor.0, cursor.1 - 1),
+            'k' => world::Coord(cursor.0, cursor.1 + 1),
+            'j' => world::Coord(cursor.0 - 1, cursor.1),
+            'l' => world::Coord(cursor.0 + 1, cursor.1),
+            ' ' => {
+                self.step = Step::Toggle(cursor);
+                return true;
+            }
+            _ => return false,
+        };
+
+        self.cursor_cell = Some(moved);
+        self.step = Step::Stay;
+        true
     }
 
-    pub fn update_window(&mut self, window: &mut world::Window) {
+    pub fn update_window(&mut self, window: &mut world::Window, world: &world::World) {
+        self.step = Step::Forward;
+
         if let Ok(c) = self.input.read_char() {
+            if self.edit(c, window) {
+                return;
+            }
+
             match c {
                 'w' => window.y -= 3,
                 's' => window.y += 3,
@@ -46,9 +231,17 @@ impl<'stdout> TerminalDisplay<'stdout> {
                     window.x = 0;
                     window.y = 0;
                 }
+                'f' => Self::center(window, world),
+                'F' => self.following = !self.following,
+                'u' => self.step = Step::Backward,
+                'r' => self.step = Step::Replay,
                 _ => (),
             }
         }
+
+        if self.following {
+            Self::center(window, world);
+        }
     }
 }
 
@@ -64,7 +257,12 @@ impl<'stdout> WorldDisplay for TerminalDisplay<'stdout> {
         let x = window.x;
         let y = window.y;
 
-        {
+        if self.renderer == Renderer::Braille {
+            for ((column, row), c) in braille(cells, window) {
+                let _ = self.cursor.goto(column, row);
+                print!("{}", c);
+            }
+        } else {
             let cursor = &self.cursor;
 
             cells.iter().for_each(|c| {
@@ -73,15 +271,59 @@ impl<'stdout> WorldDisplay for TerminalDisplay<'stdout> {
                 let _ = cursor.goto(actual_x as u16, actual_y as u16);
                 print!("@");
             });
+
+            let mut dying = vec![];
+            world.dying_cells(window, &mut dying);
+
+            let states = world.rule().states();
+
+            dying.iter().for_each(|(c, state)| {
+                let actual_x = c.0 - x;
+                let actual_y = c.1 - y;
+                let _ = cursor.goto(actual_x as u16, actual_y as u16);
+                print!("{}", decay_shade(*state, states));
+            });
+        }
+
+        if let Some(c) = self.cursor_cell {
+            let (x, y) = ((c.0 - x) as usize, (c.1 - y) as usize);
+
+            let position = match self.renderer {
+                Renderer::Ascii => (x, y),
+                Renderer::Braille => (x / 2, y / 4),
+            };
+
+            if x < window.w && y < window.h {
+                let _ = self.cursor.goto(position.0 as u16, position.1 as u16);
+                print!("+");
+            }
         }
 
         let _ = self.cursor.goto(0, 0);
-        println!(
+        print!(
             "x: {}, y: {}, population: {}, gen: {}",
             window.x,
             window.y,
-            world.population_size(),
+            population(world),
             world.gen()
         );
+
+        if self.following {
+            print!(" (following)");
+        }
+
+        if self.cursor_cell.is_some() {
+            print!(" (editing)");
+        }
+
+        if world.is_inverted() {
+            print!(" (inverted)");
+        }
+
+        if let Some(recorder) = world.recorder() {
+            print!(" {}", recorder.sparkline(SPARKLINE_WIDTH));
+        }
+
+        println!();
     }
 }
@@ -0,0 +1,235 @@
+use crate::snapshot::WorldSnapshot;
+use crate::world::World;
+use std::collections::VecDeque;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Coord;
+
+    fn glider() -> World {
+        let mut world = World::new();
+        world.action(|world| {
+            for c in [
+                Coord(1, 0),
+                Coord(2, 1),
+                Coord(0, 2),
+                Coord(1, 2),
+                Coord(2, 2),
+            ] {
+                world.make_alive(c);
+            }
+        });
+        world
+    }
+
+    #[test]
+    fn rewind_through_snapshots() {
+        let mut world = glider();
+        let mut history = History::new(4, 10);
+        let mut generations = vec![];
+
+        for _ in 0..10 {
+            history.record(&world);
+            generations.push(world.cells().to_vec());
+            world.evolve().unwrap();
+        }
+
+        for gen in (0..10).rev() {
+            assert!(history.rewind(&mut world));
+            assert_eq!(world.gen(), gen);
+            assert_eq!(world.cells(), &generations[gen][..]);
+        }
+
+        assert!(!history.rewind(&mut world));
+        assert_eq!(world.gen(), 0);
+    }
+
+    #[test]
+    fn replay_rewound_generations_with_their_edits() {
+        let mut world = glider();
+        world.start_recording();
+        let mut history = History::new(4, 10);
+        let mut generations = vec![];
+
+        for gen in 0..10 {
+            if gen == 6 {
+                world.toggle_cell(Coord(10, 10));
+                history.record_edit(&world);
+            }
+
+            history.record(&world);
+            generations.push(world.cells().to_vec());
+            world.evolve().unwrap();
+        }
+
+        assert!(generations[6].contains(&Coord(10, 10)));
+        assert!(!history.replay(&mut world));
+
+        for _ in 0..8 {
+            assert!(history.rewind(&mut world));
+        }
+
+        assert_eq!(world.gen(), 2);
+
+        for (gen, cells) in generations.iter().enumerate().skip(3) {
+            assert!(history.replay(&mut world));
+            assert_eq!(world.gen(), gen);
+            assert_eq!(world.cells(), &cells[..]);
+        }
+
+        world.evolve().unwrap();
+        assert!(!history.replay(&mut world));
+
+        // each generation is recorded once, however often it was simulated
+        let recorded = world
+            .recorder()
+            .unwrap()
+            .entries()
+            .iter()
+            .map(|e| e.generation)
+            .collect::<Vec<_>>();
+
+        assert_eq!(recorded, (0..=10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn evolving_drops_the_rewound_generations() {
+        let mut world = glider();
+        let mut history = History::new(4, 10);
+
+        for _ in 0..6 {
+            history.record(&world);
+            world.evolve().unwrap();
+        }
+
+        assert!(history.rewind(&mut world));
+        assert!(history.rewind(&mut world));
+        history.record(&world);
+        world.evolve().unwrap();
+
+        assert_eq!(world.gen(), 5);
+        assert!(!history.replay(&mut world));
+    }
+
+    #[test]
+    fn keep_the_latest_snapshots() {
+        let mut world = glider();
+        let mut history = History::new(2, 3);
+
+        for _ in 0..20 {
+            history.record(&world);
+            world.evolve().unwrap();
+        }
+
+        // snapshots of generations 14, 16 and 18 are left
+        for _ in 0..6 {
+            assert!(history.rewind(&mut world));
+        }
+
+        assert_eq!(world.gen(), 14);
+        assert!(!history.rewind(&mut world));
+        assert_eq!(world.gen(), 14);
+    }
+}
+
+/// Past generations of a world, to step back through them and replay them.
+/// A snapshot is kept every `interval` generations, and the ones in between
+/// are simulated again from the previous snapshot. Edits are kept as
+/// snapshots too, so that replaying reproduces them. Only the latest
+/// `capacity` snapshots are kept
+#[derive(Debug, Clone)]
+pub struct History {
+    interval: usize,
+    capacity: usize,
+    /// Sorted by generation
+    snapshots: VecDeque<WorldSnapshot>,
+    /// Latest generation reached, up to which rewound generations can be
+    /// replayed
+    latest: usize,
+}
+
+impl History {
+    pub fn new(interval: usize, capacity: usize) -> Self {
+        History {
+            interval: interval.max(1),
+            capacity: capacity.max(1),
+            snapshots: VecDeque::new(),
+            latest: 0,
+        }
+    }
+
+    fn push(&mut self, snapshot: WorldSnapshot) {
+        if self.snapshots.len() == self.capacity {
+            self.snapshots.pop_front();
+        }
+
+        self.snapshots.push_back(snapshot);
+    }
+
+    /// To be called on every generation, before evolving the world. The
+    /// generations that were rewound from it can't be replayed anymore
+    pub fn record(&mut self, world: &World) {
+        self.snapshots.retain(|s| s.generation <= world.gen());
+        self.latest = world.gen() + 1;
+
+        let due = self
+            .snapshots
+            .back()
+            .is_none_or(|last| world.gen() >= last.generation + self.interval);
+
+        if due {
+            self.push(world.snapshot());
+        }
+    }
+
+    /// To be called after editing the world, so that rewinding and
+    /// replaying go through the edited generation
+    pub fn record_edit(&mut self, world: &World) {
+        self.snapshots.retain(|s| s.generation < world.gen());
+        self.latest = world.gen();
+        self.push(world.snapshot());
+    }
+
+    /// Takes the world back a generation. Fails, leaving it as it is, at
+    /// generation 0 or before the oldest snapshot
+    pub fn rewind(&mut self, world: &mut World) -> bool {
+        let target = match world.gen().checked_sub(1) {
+            Some(target) => target,
+            None => return false,
+        };
+
+        let snapshot = match self.snapshots.iter().rev().find(|s| s.generation <= target) {
+            Some(snapshot) => snapshot.clone(),
+            None => return false,
+        };
+
+        world.restore(snapshot);
+
+        while world.gen() < target {
+            if world.evolve().is_err() {
+                break;
+            }
+        }
+
+        true
+    }
+
+    /// Takes the world forward a generation that was rewound, with the edits
+    /// made to it. Fails, leaving it as it is, at the latest generation
+    pub fn replay(&mut self, world: &mut World) -> bool {
+        if world.gen() >= self.latest || world.evolve().is_err() {
+            return false;
+        }
+
+        // the snapshots that aren't edits are the generation as it evolved,
+        // so restoring them changes nothing
+        let snapshot = self.snapshots.iter().find(|s| s.generation == world.gen());
+
+        if let Some(snapshot) = snapshot {
+            world.restore(snapshot.clone());
+        }
+
+        true
+    }
+}
@@ -1,5 +1,20 @@
+pub mod census;
+pub mod cli;
 pub mod common;
 pub mod display;
+pub mod history;
+pub mod ltl;
+pub mod macrocell;
+pub mod parallel;
+pub mod pattern;
+pub mod plaintext;
+pub mod recorder;
 pub mod rle;
+pub mod rule;
+pub mod scene;
+pub mod search;
+pub mod snapshot;
+pub mod tiled;
+pub mod topology;
 pub mod utils;
 pub mod world;
@@ -0,0 +1,352 @@
+use crate::common;
+use crate::parallel::Parallelism;
+use crate::rule::{CellState, Neighboorhood, Rule};
+use crate::world::{Coord, Coords};
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut cells: Coords) -> Coords {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn range_one_is_life() {
+        let rule = "R1,C0,M0,S2..3,B3..3,NM".parse::<Rule>().unwrap();
+        let blinker = sorted(vec![Coord(0, -1), Coord(0, 0), Coord(0, 1)]);
+
+        let (alive, dying) = evolve(&blinker, &[], &rule, &Parallelism::new());
+
+        assert_eq!(alive, vec![Coord(-1, 0), Coord(0, 0), Coord(1, 0)]);
+        assert!(dying.is_empty());
+    }
+
+    /// Next generation of a range 2 Moore rule, counting each cell around
+    /// the block at `(x, y)`
+    fn next_block(rule: &Rule, x: common::Int, y: common::Int) -> Coords {
+        let alive = |c: Coord| c.0 >= x && c.0 <= x + 1 && c.1 >= y && c.1 <= y + 1;
+
+        let mut next = vec![];
+
+        for cx in x - 2..=x + 3 {
+            for cy in y - 2..=y + 3 {
+                let count = (-2..=2)
+                    .flat_map(|dx| (-2..=2).map(move |dy| Coord(cx + dx, cy + dy)))
+                    .filter(|c| alive(*c))
+                    .count();
+
+                let state = match alive(Coord(cx, cy)) {
+                    true => CellState::Alive,
+                    false => CellState::Dead,
+                };
+
+                if rule.mutate_count(state, count) == CellState::Alive {
+                    next.push(Coord(cx, cy));
+                }
+            }
+        }
+
+        sorted(next)
+    }
+
+    #[test]
+    fn distant_patterns_evolve_separately() {
+        let rule = "R2,C0,M1,S3..5,B3..4,NM".parse::<Rule>().unwrap();
+
+        // across the edges of the tiles, and far away
+        let blocks = [(-1, 63), (200_000, -200_000)];
+
+        let alive = blocks
+            .iter()
+            .flat_map(|&(x, y)| {
+                vec![
+                    Coord(x, y),
+                    Coord(x + 1, y),
+                    Coord(x, y + 1),
+                    Coord(x + 1, y + 1),
+                ]
+            })
+            .collect();
+
+        let expected = blocks
+            .iter()
+            .flat_map(|&(x, y)| next_block(&rule, x, y))
+            .collect();
+
+        let (next, _) = evolve(&sorted(alive), &[], &rule, &Parallelism::new());
+
+        assert!(next.len() > 8);
+        assert_eq!(next, sorted(expected));
+    }
+
+    #[test]
+    fn middle_counts_the_cell_itself() {
+        // a lonely cell only survives if it counts itself
+        let rule = "R2,C0,M1,S1..1,B9..9,NM".parse::<Rule>().unwrap();
+        assert_eq!(
+            evolve(&[Coord(3, 4)], &[], &rule, &Parallelism::new()).0,
+            vec![Coord(3, 4)]
+        );
+
+        let rule = "R2,C0,M0,S1..1,B9..9,NM".parse::<Rule>().unwrap();
+        assert!(evolve(&[Coord(3, 4)], &[], &rule, &Parallelism::new())
+            .0
+            .is_empty());
+    }
+
+    #[test]
+    fn von_neumann_range_is_a_diamond() {
+        let rule = "R2,C0,M0,S,B1,NN".parse::<Rule>().unwrap();
+
+        let (alive, _) = evolve(&[Coord(0, 0)], &[], &rule, &Parallelism::new());
+
+        assert_eq!(alive.len(), 12);
+        assert!(alive.contains(&Coord(1, 1)));
+        assert!(alive.contains(&Coord(0, -2)));
+        assert!(!alive.contains(&Coord(2, 1)));
+    }
+
+    #[test]
+    fn dying_cells_decay_and_block_births() {
+        let rule = "R1,C4,M0,S,B1,NM".parse::<Rule>().unwrap();
+
+        let (alive, dying) = evolve(
+            &[Coord(0, 0)],
+            &[(Coord(1, 0), 2)],
+            &rule,
+            &Parallelism::new(),
+        );
+
+        assert_eq!(alive.len(), 7);
+        assert!(!alive.contains(&Coord(1, 0)));
+        assert_eq!(dying, vec![(Coord(0, 0), 2), (Coord(1, 0), 3)]);
+    }
+}
+
+/// Live cells around a tile on a dense grid, with a summed-area table for
+/// counting them over any rectangle in constant time
+#[derive(Debug, Clone)]
+struct Grid {
+    /// Wider than `common::Int`, as the grid may go past its limits
+    origin: (i64, i64),
+    size: usize,
+    /// 0 for dead, 1 for alive, 2 for dying cells
+    cells: Vec<u8>,
+    /// Live cells above and to the left of each position, with an extra
+    /// leading row and column of zeroes
+    sums: Vec<u32>,
+}
+
+impl Grid {
+    /// Square grid from `origin`, with the cells inside it
+    fn new(origin: (i64, i64), size: usize, alive: &[Coord], dying: &[(Coord, u8)]) -> Self {
+        let mut grid = Grid {
+            origin,
+            size,
+            cells: vec![0; size * size],
+            sums: vec![0; (size + 1) * (size + 1)],
+        };
+
+        for c in alive {
+            if let Some(i) = grid.index(*c) {
+                grid.cells[i] = 1;
+            }
+        }
+
+        for (c, _) in dying {
+            if let Some(i) = grid.index(*c) {
+                grid.cells[i] = 2;
+            }
+        }
+
+        for y in 0..size {
+            let mut row = 0;
+
+            for x in 0..size {
+                row += (grid.cells[y * size + x] == 1) as u32;
+                grid.sums[(y + 1) * (size + 1) + x + 1] = grid.sums[y * (size + 1) + x + 1] + row;
+            }
+        }
+
+        grid
+    }
+
+    fn index(&self, c: Coord) -> Option<usize> {
+        let x = c.0 as i64 - self.origin.0;
+        let y = c.1 as i64 - self.origin.1;
+
+        if x < 0 || y < 0 || x as usize >= self.size || y as usize >= self.size {
+            return None;
+        }
+
+        Some(y as usize * self.size + x as usize)
+    }
+
+    /// Live cells in the rectangle from (x0, y0) to (x1, y1), inclusive and
+    /// clipped to the grid
+    fn count(&self, x0: isize, y0: isize, x1: isize, y1: isize) -> u32 {
+        let clip = |v: isize| v.clamp(0, self.size as isize) as usize;
+
+        let (x0, x1) = (clip(x0), clip(x1 + 1));
+        let (y0, y1) = (clip(y0), clip(y1 + 1));
+
+        if x0 >= x1 || y0 >= y1 {
+            return 0;
+        }
+
+        let at = |x: usize, y: usize| self.sums[y * (self.size + 1) + x];
+
+        at(x1, y1) + at(x0, y0) - at(x0, y1) - at(x1, y0)
+    }
+}
+
+/// Cells next to a tile are at most a range away, so they are in the tiles
+/// around it. The size is a power of two, so tiles don't cross the limits
+/// of `common::Int`
+fn tile_size(range: common::Int) -> common::Int {
+    (range.max(64) as u32).next_power_of_two() as common::Int
+}
+
+/// The live and dying cells that the tiles of the next generation need,
+/// sorted by tile: each tile with the live cells at most `range` away from
+/// it, and the dying cells inside it
+type Tiles = Vec<((common::Int, common::Int), Coords, Vec<(Coord, u8)>)>;
+
+fn tiles(alive: &[Coord], dying: &[(Coord, u8)], range: common::Int) -> Tiles {
+    let size = tile_size(range);
+    let tile_of = |v: common::Int| v.div_euclid(size);
+    let reach =
+        |v: common::Int| tile_of(v.saturating_sub(range))..=tile_of(v.saturating_add(range));
+
+    let mut tiles = HashMap::<_, (Coords, Vec<(Coord, u8)>)>::new();
+
+    for c in alive {
+        for x in reach(c.0) {
+            for y in reach(c.1) {
+                tiles.entry((x, y)).or_default().0.push(*c);
+            }
+        }
+    }
+
+    for d in dying {
+        if let Some(tile) = tiles.get_mut(&(tile_of(d.0 .0), tile_of(d.0 .1))) {
+            tile.1.push(*d);
+        }
+    }
+
+    let mut tiles = tiles
+        .into_iter()
+        .map(|(tile, (alive, dying))| (tile, alive, dying))
+        .collect::<Tiles>();
+
+    tiles.sort_unstable_by_key(|t| t.0);
+    tiles
+}
+
+/// Next generation of a Larger than Life rule, counting neighboors with a
+/// summed-area table on each tile with live cells in range, so that only the
+/// area around the cells is visited, however far apart they are.
+///
+/// `alive` must be sorted by `Coord`, and so are the returned cells
+pub fn evolve(
+    alive: &[Coord],
+    dying: &[(Coord, u8)],
+    rule: &Rule,
+    parallelism: &Parallelism,
+) -> (Coords, Vec<(Coord, u8)>) {
+    let (range, von_neumann, middle) = match rule.neighboorhood() {
+        Neighboorhood::Range {
+            range,
+            von_neumann,
+            middle,
+            ..
+        } => (*range, *von_neumann, *middle),
+        _ => panic!("not a Larger than Life rule"),
+    };
+
+    let mut next_dying = parallelism.flat_map(dying, |(c, s)| {
+        match rule.mutate_count(CellState::Dying(*s), 0) {
+            CellState::Dying(s) => Some((*c, s)),
+            _ => None,
+        }
+    });
+
+    if alive.is_empty() {
+        return (Coords::new(), next_dying);
+    }
+
+    let size = tile_size(range);
+    let r = range as isize;
+    let tiles = tiles(alive, dying, range);
+
+    let states = parallelism.for_items_of((size * size) as usize).flat_map(
+        &tiles,
+        |((tx, ty), alive, dying)| {
+            // the tile, with a margin of `range` around it
+            let origin = (
+                *tx as i64 * size as i64 - range as i64,
+                *ty as i64 * size as i64 - range as i64,
+            );
+            let grid = Grid::new(origin, (size + 2 * range) as usize, alive, dying);
+
+            let inside = range as usize..(size + range) as usize;
+
+            inside
+                .clone()
+                .flat_map(move |y| inside.clone().map(move |x| (x, y)))
+                .filter_map(move |(x, y)| {
+                    let (xi, yi) = (x as isize, y as isize);
+
+                    let mut count = if von_neumann {
+                        (-r..=r)
+                            .map(|dy| {
+                                let w = r - dy.abs();
+                                grid.count(xi - w, yi + dy, xi + w, yi + dy)
+                            })
+                            .sum()
+                    } else {
+                        grid.count(xi - r, yi - r, xi + r, yi + r)
+                    };
+
+                    let state = match grid.cells[y * grid.size + x] {
+                        1 => CellState::Alive,
+                        2 => return None,
+                        _ => CellState::Dead,
+                    };
+
+                    if state == CellState::Alive && !middle {
+                        count -= 1;
+                    }
+
+                    match rule.mutate_count(state, count as usize) {
+                        CellState::Dead => None,
+                        next => {
+                            // inside a tile, so within the limits of `common::Int`
+                            let c = Coord(
+                                (grid.origin.0 + x as i64) as common::Int,
+                                (grid.origin.1 + y as i64) as common::Int,
+                            );
+                            Some((c, next))
+                        }
+                    }
+                })
+        },
+    );
+
+    let mut next_alive = Coords::with_capacity(states.len());
+
+    for (c, state) in states {
+        match state {
+            CellState::Dying(s) => next_dying.push((c, s)),
+            _ => next_alive.push(c),
+        }
+    }
+
+    parallelism.sort_unstable(&mut next_alive);
+    parallelism.sort_unstable_by_key(&mut next_dying, |d| d.0);
+
+    (next_alive, next_dying)
+}
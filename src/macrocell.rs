@@ -0,0 +1,314 @@
+use crate::common::Int;
+use crate::rle::LifePlaceMaker;
+use crate::world::Coord;
+use std::fmt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeStorage {
+        cells: Vec<(Coord, u8)>,
+    }
+
+    impl LifePlaceMaker for FakeStorage {
+        fn make_cell_alive(&mut self, coord: Coord) {
+            self.cells.push((coord, 1));
+        }
+
+        fn make_cell_state(&mut self, coord: Coord, state: u8) {
+            self.cells.push((coord, state));
+        }
+    }
+
+    #[test]
+    fn parse_glider() {
+        let content = "[M2] (golly 4.2)\n#R B3/S23\n#G 24\n#C a glider\n.*$..*$***$\n4 0 0 0 1\n";
+
+        let mut storage = FakeStorage { cells: vec![] };
+        let parsed = parse(content, &mut storage).unwrap();
+
+        assert_eq!(parsed.rule, Some("B3/S23".to_string()));
+        assert_eq!(parsed.generation, Some(24));
+        assert_eq!(parsed.comments, vec!["a glider"]);
+        assert_eq!((parsed.x, parsed.y), (3, 3));
+
+        // the root node is centered on the origin
+        assert_eq!(
+            storage.cells,
+            vec![
+                (Coord(1, 0), 1),
+                (Coord(2, 1), 1),
+                (Coord(0, 2), 1),
+                (Coord(1, 2), 1),
+                (Coord(2, 2), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_multi_state_nodes() {
+        let content = "[M2]\n1 0 1 2 0\n1 3 0 0 0\n2 1 0 0 2\n";
+
+        let mut storage = FakeStorage { cells: vec![] };
+        parse(content, &mut storage).unwrap();
+
+        assert_eq!(
+            storage.cells,
+            vec![(Coord(-1, -2), 1), (Coord(-2, -1), 2), (Coord(0, 0), 3)]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_nodes() {
+        let mut storage = FakeStorage { cells: vec![] };
+
+        let error = parse("[M2]\n.*$\n4 0 1 0 3\n", &mut storage).unwrap_err();
+        assert_eq!(error.to_string(), "line 3: no node 3 before this one");
+
+        let error = parse("[M2]\n.*$\n5 0 1 0 0\n", &mut storage).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 3: node 1 is of level 3, expected 4"
+        );
+
+        let error = parse("x = 1, y = 1\no!", &mut storage).unwrap_err();
+        assert_eq!(error.to_string(), "line 1: missing [M2] header");
+    }
+}
+
+/// Header of a macrocell (`.mc`) file, with the size of its bounding box
+#[derive(Debug)]
+pub struct MacrocellLife {
+    pub x: Int,
+    pub y: Int,
+    pub rule: Option<String>,
+    pub generation: Option<usize>,
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseMacrocellError {
+    pub line: usize,
+    reason: String,
+}
+
+impl fmt::Display for ParseMacrocellError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseMacrocellError {}
+
+/// Coordinates can't hold the cells of larger nodes
+const MAX_LEVEL: u32 = 31;
+
+enum Node {
+    /// An 8x8 square of cells, as a level 3 node
+    Leaf(Vec<(Int, Int)>),
+    /// The states of 2x2 cells of a multi-state pattern
+    States([u8; 4]),
+    /// Indexes of the nw, ne, sw and se quadrants, 0 for empty ones
+    Inner { level: u32, children: [usize; 4] },
+}
+
+impl Node {
+    fn level(&self) -> u32 {
+        match self {
+            Node::Leaf(_) => 3,
+            Node::States(_) => 1,
+            Node::Inner { level, .. } => *level,
+        }
+    }
+}
+
+fn parse_leaf(line: &str) -> Result<Node, String> {
+    let mut cells = vec![];
+    let (mut x, mut y) = (0, 0);
+
+    for c in line.chars() {
+        if c != '$' && (x >= 8 || y >= 8) {
+            return Err("leaf nodes are 8x8 cells".to_string());
+        }
+
+        match c {
+            '.' => x += 1,
+            '*' => {
+                cells.push((x, y));
+                x += 1;
+            }
+            '$' => {
+                x = 0;
+                y += 1;
+            }
+            c => return Err(format!("unexpected '{}' in a leaf node", c)),
+        }
+    }
+
+    Ok(Node::Leaf(cells))
+}
+
+fn parse_inner(line: &str, nodes: &[Node]) -> Result<Node, String> {
+    let numbers = line
+        .split_whitespace()
+        .map(|number| number.parse::<usize>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid node '{}'", line))?;
+
+    let (level, children) = match numbers.as_slice() {
+        &[level, nw, ne, sw, se] => (level as u32, [nw, ne, sw, se]),
+        _ => return Err(format!("invalid node '{}'", line)),
+    };
+
+    if level == 0 || level > MAX_LEVEL {
+        return Err(format!("levels go from 1 to {}", MAX_LEVEL));
+    }
+
+    if level == 1 {
+        let states = children.map(|state| state.min(255) as u8);
+        return Ok(Node::States(states));
+    }
+
+    for &child in children.iter().filter(|&&child| child != 0) {
+        let node = nodes
+            .get(child - 1)
+            .ok_or_else(|| format!("no node {} before this one", child))?;
+
+        if node.level() != level - 1 {
+            return Err(format!(
+                "node {} is of level {}, expected {}",
+                child,
+                node.level(),
+                level - 1
+            ));
+        }
+    }
+
+    Ok(Node::Inner { level, children })
+}
+
+/// Places the cells of a node whose top-left corner is at `origin`, growing
+/// the bounding box of the placed cells
+fn place(
+    nodes: &[Node],
+    index: usize,
+    origin: Coord,
+    storage: &mut dyn LifePlaceMaker,
+    bounds: &mut Option<(Coord, Coord)>,
+) {
+    let mut place_cell = |coord: Coord, state: u8| {
+        storage.make_cell_state(coord, state);
+
+        let (min, max) = bounds.get_or_insert((coord, coord));
+        *min = Coord(min.0.min(coord.0), min.1.min(coord.1));
+        *max = Coord(max.0.max(coord.0), max.1.max(coord.1));
+    };
+
+    match &nodes[index - 1] {
+        Node::Leaf(cells) => {
+            for (x, y) in cells {
+                place_cell(Coord(origin.0 + x, origin.1 + y), 1);
+            }
+        }
+        Node::States(states) => {
+            for (i, &state) in states.iter().enumerate() {
+                if state != 0 {
+                    let (x, y) = ((i % 2) as Int, (i / 2) as Int);
+                    place_cell(Coord(origin.0 + x, origin.1 + y), state);
+                }
+            }
+        }
+        Node::Inner { level, children } => {
+            let half = 1 << (level - 1);
+
+            for (i, &child) in children.iter().enumerate() {
+                if child != 0 {
+                    let (x, y) = ((i % 2) as Int * half, (i / 2) as Int * half);
+                    place(
+                        nodes,
+                        child,
+                        Coord(origin.0 + x, origin.1 + y),
+                        storage,
+                        bounds,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Parses Golly's macrocell format, a quadtree whose nodes are listed
+/// children first, as written by hashlife. The last node is the root, and is
+/// centered on the origin. Documented at
+/// http://golly.sourceforge.net/Help/formats.html#mc
+pub fn parse(
+    content: &str,
+    storage: &mut dyn LifePlaceMaker,
+) -> Result<MacrocellLife, ParseMacrocellError> {
+    let mut life = MacrocellLife {
+        x: 0,
+        y: 0,
+        rule: None,
+        generation: None,
+        comments: vec![],
+    };
+
+    let mut lines = content.lines().enumerate();
+
+    if !lines
+        .next()
+        .is_some_and(|(_, header)| header.starts_with("[M2]"))
+    {
+        return Err(ParseMacrocellError {
+            line: 1,
+            reason: "missing [M2] header".to_string(),
+        });
+    }
+
+    let mut nodes = vec![];
+
+    for (i, line) in lines {
+        let line = line.trim_end();
+
+        let node = match line.chars().next() {
+            None => continue,
+            Some('#') => {
+                match line.split_at(line.len().min(2)) {
+                    ("#R", rule) => life.rule = Some(rule.trim().to_string()),
+                    ("#G", generation) => life.generation = generation.trim().parse().ok(),
+                    (_, comment) => life.comments.push(comment.trim().to_string()),
+                }
+
+                continue;
+            }
+            Some('.') | Some('*') | Some('$') => parse_leaf(line),
+            Some(_) => parse_inner(line, &nodes),
+        };
+
+        nodes.push(node.map_err(|reason| ParseMacrocellError {
+            line: i + 1,
+            reason,
+        })?);
+    }
+
+    if let Some(root) = nodes.last() {
+        let corner = -(1 << (root.level() - 1));
+        let mut bounds = None;
+
+        place(
+            &nodes,
+            nodes.len(),
+            Coord(corner, corner),
+            storage,
+            &mut bounds,
+        );
+
+        if let Some((min, max)) = bounds {
+            life.x = max.0 - min.0 + 1;
+            life.y = max.1 - min.1 + 1;
+        }
+    }
+
+    Ok(life)
+}
@@ -1,37 +1,12 @@
-use gameoflife::rle;
-use gameoflife::utils::WorldLifePlaceMaker;
-use gameoflife::world::{Coord, Coords, World};
+use gameoflife::cli;
+use std::env;
+use std::process;
 
 fn main() {
-    use gameoflife::display::*;
-    use std::env;
-    use std::fs;
+    let args = env::args().skip(1).collect::<Vec<_>>();
 
-    let mut world = World::new();
-
-    world.action(|world| {
-        let args: Vec<String> = env::args().collect();
-        let filename = &args[1];
-        let mut placemaker = WorldLifePlaceMaker::new(Coord(0, 0), world);
-        let life_content = fs::read_to_string(filename).unwrap();
-        let _ = rle::parse(&life_content, &mut placemaker).unwrap();
-    });
-
-    let mut display = TerminalDisplay::new();
-    let mut window = display.best_window(0, 0);
-    let mut cells = Coords::new();
-
-    for _ in 1..=10 {
-        //loop {
-        println!(
-            "Generation {}, population: {}",
-            world.gen(),
-            world.population_size()
-        );
-        cells.clear();
-        world.live_cells(&window, &mut cells);
-        //display.display(&cells, &mut window, &world);
-        world.evolve();
-        //display.update_window(&mut window);
+    if let Err(error) = cli::main(&args) {
+        eprintln!("gameoflife: {}", error);
+        process::exit(error.exit_code());
     }
 }
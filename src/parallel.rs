@@ -0,0 +1,200 @@
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+#[cfg(feature = "parallel")]
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_inputs_run_serially() {
+        let parallelism = Parallelism::new().with_threshold(4);
+
+        assert!(!parallelism.is_parallel(3));
+        assert_eq!(parallelism.is_parallel(4), cfg!(feature = "parallel"));
+        assert!(!Parallelism::serial().is_parallel(usize::MAX - 1));
+
+        let rows = parallelism.for_items_of(2);
+        assert_eq!(rows.threshold(), 2);
+        assert_eq!(parallelism.for_items_of(100).threshold(), 1);
+        assert_eq!(
+            Parallelism::serial().for_items_of(100).threshold(),
+            usize::MAX
+        );
+    }
+
+    #[test]
+    fn serial_and_parallel_agree() {
+        let items = (0..10000).rev().collect::<Vec<u32>>();
+
+        for parallelism in [Parallelism::serial(), Parallelism::new().with_threshold(1)] {
+            let mut sorted = items.clone();
+            parallelism.sort_unstable(&mut sorted);
+            assert_eq!(sorted, (0..10000).collect::<Vec<_>>());
+
+            let pairs = parallelism.flat_map(&items, |i| vec![*i; 2]);
+            assert_eq!(pairs.len(), 20000);
+            assert_eq!(&pairs[..4], &[9999, 9999, 9998, 9998]);
+
+            assert_eq!(
+                parallelism.map(&items[..3], |i| i * 2),
+                vec![19998, 19996, 19994]
+            );
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn runs_inside_the_given_pool() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(2)
+            .build()
+            .unwrap();
+        let parallelism = Parallelism::new()
+            .with_threshold(1)
+            .with_pool(Arc::new(pool));
+
+        let threads = parallelism.map(&[0; 8], |_| rayon::current_num_threads());
+
+        assert_eq!(threads, vec![2; 8]);
+    }
+}
+
+/// Inputs shorter than this are processed serially by default, as splitting
+/// them across threads costs more than it saves on small patterns
+pub const DEFAULT_THRESHOLD: usize = 4096;
+
+/// How the engines split their work across threads: only inputs of at least
+/// `threshold` items are split, on the given rayon thread pool or on the
+/// global one.
+///
+/// Without the `parallel` feature, rayon isn't used at all and everything runs
+/// on the calling thread
+#[derive(Debug, Clone)]
+pub struct Parallelism {
+    threshold: usize,
+    #[cfg(feature = "parallel")]
+    pool: Option<Arc<rayon::ThreadPool>>,
+}
+
+impl Default for Parallelism {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parallelism {
+    pub fn new() -> Self {
+        Parallelism {
+            threshold: DEFAULT_THRESHOLD,
+            #[cfg(feature = "parallel")]
+            pool: None,
+        }
+    }
+
+    /// Never splits any work
+    pub fn serial() -> Self {
+        Parallelism::new().with_threshold(usize::MAX)
+    }
+
+    pub fn with_threshold(mut self, threshold: usize) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Runs the parallel work on `pool` instead of on rayon's global pool, for
+    /// when the application has a pool of its own
+    #[cfg(feature = "parallel")]
+    pub fn with_pool(mut self, pool: Arc<rayon::ThreadPool>) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// For inputs whose items each take about `work` times as long as the
+    /// ones the threshold is meant for, like rows of cells. Serial stays serial
+    pub fn for_items_of(&self, work: usize) -> Self {
+        let mut parallelism = self.clone();
+
+        if self.threshold != usize::MAX {
+            parallelism.threshold = (self.threshold / work.max(1)).max(1);
+        }
+
+        parallelism
+    }
+
+    pub fn threshold(&self) -> usize {
+        self.threshold
+    }
+
+    pub fn is_parallel(&self, len: usize) -> bool {
+        cfg!(feature = "parallel") && len >= self.threshold
+    }
+
+    #[cfg(feature = "parallel")]
+    fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(f),
+            None => f(),
+        }
+    }
+
+    pub fn sort_unstable<T: Ord + Send>(&self, items: &mut [T]) {
+        #[cfg(feature = "parallel")]
+        if self.is_parallel(items.len()) {
+            return self.install(|| items.par_sort_unstable());
+        }
+
+        items.sort_unstable();
+    }
+
+    pub fn sort_unstable_by_key<T: Send, K: Ord>(
+        &self,
+        items: &mut [T],
+        key: impl Fn(&T) -> K + Sync + Send,
+    ) {
+        #[cfg(feature = "parallel")]
+        if self.is_parallel(items.len()) {
+            return self.install(|| items.par_sort_unstable_by_key(key));
+        }
+
+        items.sort_unstable_by_key(key);
+    }
+
+    /// Results are in the order of the items
+    pub fn map<T: Sync, R: Send>(&self, items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+        #[cfg(feature = "parallel")]
+        if self.is_parallel(items.len()) {
+            return self.install(|| items.par_iter().map(f).collect());
+        }
+
+        items.iter().map(f).collect()
+    }
+
+    /// Results are in the order of the items
+    pub fn flat_map<T: Sync, I>(
+        &self,
+        items: &[T],
+        f: impl Fn(&T) -> I + Sync + Send,
+    ) -> Vec<I::Item>
+    where
+        I: IntoIterator,
+        I::Item: Send,
+    {
+        #[cfg(feature = "parallel")]
+        if self.is_parallel(items.len()) {
+            return self.install(|| items.par_iter().flat_map_iter(f).collect());
+        }
+
+        items.iter().flat_map(f).collect()
+    }
+
+    pub fn for_each_mut<T: Send>(&self, items: &mut [T], f: impl Fn(&mut T) + Sync + Send) {
+        #[cfg(feature = "parallel")]
+        if self.is_parallel(items.len()) {
+            return self.install(|| items.par_iter_mut().for_each(f));
+        }
+
+        items.iter_mut().for_each(f);
+    }
+}
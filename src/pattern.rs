@@ -0,0 +1,252 @@
+use crate::rule::Rule;
+use crate::world::{Coord, Coords, Window, World};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glider() -> Pattern {
+        Pattern::new(
+            Rule::default(),
+            vec![
+                Coord(11, 5),
+                Coord(12, 6),
+                Coord(10, 7),
+                Coord(11, 7),
+                Coord(12, 7),
+            ],
+        )
+    }
+
+    #[test]
+    fn pattern_of_a_world() {
+        let pattern = glider();
+
+        let mut world = World::new();
+        world.action(|world| pattern.cells.iter().for_each(|c| world.make_alive(*c)));
+
+        assert_eq!(Pattern::from_world(&world), Some(pattern.clone()));
+        assert_eq!(pattern.position(), Coord(10, 5));
+    }
+
+    #[test]
+    fn pattern_of_an_inverted_world() {
+        let rule = "B013/S0124:T4,4".parse::<Rule>().unwrap();
+        let mut world = World::with_rule(rule.clone());
+        world.action(|world| world.make_alive(Coord(0, 0)));
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+
+        let pattern = Pattern::from_world(&world).unwrap();
+        let mut cells = vec![];
+        world.live_cells(&Window::new(-2, -2, 4, 4), &mut cells);
+
+        assert_eq!(pattern, Pattern::new(rule, cells));
+        assert_eq!(pattern.cells.len(), world.live_population().unwrap());
+        assert!(!pattern.cells.is_empty());
+
+        let mut world = World::with_rule("B0123478/S01234678".parse().unwrap());
+        world.action(|world| world.make_alive(Coord(0, 0)));
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+        assert_eq!(Pattern::from_world(&world), None);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn cells_out_of_range() {
+        let max = crate::common::Int::MAX;
+        let json = format!(
+            r#"{{"rule":"B3/S23","position":[{},0],"cells":[1,0]}}"#,
+            max
+        );
+
+        let error = serde_json::from_str::<Pattern>(&json).unwrap_err();
+        assert!(error.to_string().starts_with("cells out of range"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_compactly() {
+        let pattern = glider();
+        let json = serde_json::to_string(&pattern).unwrap();
+
+        assert_eq!(
+            json,
+            r#"{"rule":"B3/S23","position":[10,5],"rle":"bo$2bo$3o!"}"#
+        );
+        assert_eq!(serde_json::from_str::<Pattern>(&json).unwrap(), pattern);
+
+        let packed = r#"{"rule":"B3/S23","position":[10,5],"cells":[1,0,2,1,0,2,1,2,2,2]}"#;
+        assert_eq!(serde_json::from_str::<Pattern>(packed).unwrap(), pattern);
+
+        let window = crate::world::Window::new(-3, 4, 80, 24);
+        assert_eq!(
+            serde_json::to_string(&window).unwrap(),
+            r#"{"w":80,"h":24,"x":-3,"y":4}"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_invalid_patterns() {
+        let error = serde_json::from_str::<Pattern>(r#"{"rule":"B3/S23"}"#).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("either rle or cells is needed"));
+
+        let error =
+            serde_json::from_str::<Pattern>(r#"{"rule":"B3/S23","cells":[1]}"#).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("cells must be pairs of x and y"));
+
+        let error = serde_json::from_str::<Pattern>(r#"{"rule":"B9/S","rle":"o!"}"#).unwrap_err();
+        assert!(error.to_string().starts_with("invalid rule \"B9/S\""));
+    }
+}
+
+/// The live cells of a pattern and its rule. With the `serde` feature, it's
+/// serialized as its rule, the top-left corner of its bounding box and its
+/// RLE body, like `{"rule":"B3/S23","position":[10,5],"rle":"bo$2bo$3o!"}`.
+/// Instead of `rle`, it can also be deserialized from `cells`, a list of x
+/// and y pairs relative to the position, like `[1,0,2,1,0,2,1,2,2,2]`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "compact::CompactPattern", try_from = "compact::CompactPattern")
+)]
+pub struct Pattern {
+    pub rule: Rule,
+    /// Sorted
+    pub cells: Vec<Coord>,
+}
+
+impl Pattern {
+    pub fn new(rule: Rule, mut cells: Vec<Coord>) -> Self {
+        cells.sort_unstable();
+        cells.dedup();
+        Pattern { rule, cells }
+    }
+
+    /// The live cells of the world. None if it's inverted on an unbounded
+    /// grid, where infinitely many cells are alive
+    pub fn from_world(world: &World) -> Option<Self> {
+        if !world.is_inverted() {
+            return Some(Pattern {
+                rule: world.rule().clone(),
+                cells: world.cells().to_vec(),
+            });
+        }
+
+        let grid = world.rule().bounded_grid()?;
+        grid.area()?;
+
+        let (w, h) = (grid.width, grid.height);
+        let window = Window::new(-(w / 2), -(h / 2), w as usize, h as usize);
+
+        let mut cells = Coords::new();
+        world.live_cells(&window, &mut cells);
+
+        Some(Pattern {
+            rule: world.rule().clone(),
+            cells,
+        })
+    }
+
+    /// Top-left corner of the bounding box of the cells
+    pub fn position(&self) -> Coord {
+        let x = self.cells.first().map_or(0, |c| c.0);
+        let y = self.cells.iter().map(|c| c.1).min().unwrap_or(0);
+        Coord(x, y)
+    }
+}
+
+#[cfg(feature = "serde")]
+mod compact {
+    use super::Pattern;
+    use crate::common::Int;
+    use crate::rle::{self, LifePlaceMaker, LreWriter};
+    use crate::rule::Rule;
+    use crate::world::Coord;
+    use std::convert::TryFrom;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    pub struct CompactPattern {
+        rule: Rule,
+        #[serde(default)]
+        position: Option<Coord>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        rle: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cells: Option<Vec<Int>>,
+    }
+
+    struct Cells(Vec<Coord>);
+
+    impl LifePlaceMaker for Cells {
+        fn make_cell_alive(&mut self, coord: Coord) {
+            self.0.push(coord);
+        }
+    }
+
+    impl From<Pattern> for CompactPattern {
+        fn from(pattern: Pattern) -> Self {
+            let mut out = Vec::new();
+            LreWriter::new()
+                .rule(&pattern.rule.to_string())
+                .write(&pattern.cells, &mut out)
+                .expect("writing to memory doesn't fail");
+
+            // the body, without the header line
+            let rle = String::from_utf8_lossy(&out)
+                .lines()
+                .skip(1)
+                .collect::<String>();
+
+            CompactPattern {
+                position: Some(pattern.position()),
+                rule: pattern.rule,
+                rle: Some(rle),
+                cells: None,
+            }
+        }
+    }
+
+    impl TryFrom<CompactPattern> for Pattern {
+        type Error = String;
+
+        fn try_from(compact: CompactPattern) -> Result<Self, Self::Error> {
+            let position = compact.position.unwrap_or(Coord(0, 0));
+
+            let cells = match (compact.rle, compact.cells) {
+                (Some(rle), _) => {
+                    let content = format!(
+                        "#CXRLE Pos={},{}\nx = 0, y = 0\n{}",
+                        position.0, position.1, rle
+                    );
+
+                    let mut cells = Cells(vec![]);
+                    rle::parse(&content, &mut cells).map_err(|error| error.to_string())?;
+                    cells.0
+                }
+                (None, Some(cells)) if cells.len() % 2 == 0 => cells
+                    .chunks(2)
+                    .map(|c| {
+                        let x = position.0.checked_add(c[0])?;
+                        let y = position.1.checked_add(c[1])?;
+                        Some(Coord(x, y))
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| "cells out of range".to_string())?,
+                (None, Some(_)) => return Err("cells must be pairs of x and y".to_string()),
+                (None, None) => return Err("either rle or cells is needed".to_string()),
+            };
+
+            Ok(Pattern::new(compact.rule, cells))
+        }
+    }
+}
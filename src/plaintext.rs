@@ -0,0 +1,214 @@
+use crate::common;
+use crate::rle::LifePlaceMaker;
+use crate::world::Coord;
+use std::fmt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FakeStorage {
+        cells: Vec<Coord>,
+    }
+
+    impl LifePlaceMaker for FakeStorage {
+        fn make_cell_alive(&mut self, coord: Coord) {
+            self.cells.push(coord);
+        }
+    }
+
+    #[test]
+    fn parse_glider() {
+        let content = "!Name: Glider\n!The smallest spaceship\n.O\n..O\nOOO\n";
+
+        let mut storage = FakeStorage { cells: vec![] };
+        let parsed = parse(content, &mut storage).unwrap();
+
+        assert_eq!(parsed.name, Some("Glider".to_string()));
+        assert_eq!(parsed.comments, vec!["The smallest spaceship"]);
+        assert_eq!((parsed.x, parsed.y), (3, 3));
+        assert_eq!(
+            storage.cells,
+            vec![
+                Coord(1, 0),
+                Coord(2, 1),
+                Coord(0, 2),
+                Coord(1, 2),
+                Coord(2, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_cell() {
+        let mut storage = FakeStorage { cells: vec![] };
+        let error = parse("!Name: x\n.O\n.x\n", &mut storage).unwrap_err();
+
+        assert_eq!(error.line, 3);
+        assert_eq!(
+            error.to_string(),
+            "line 3: unexpected 'x', cells must be '.' or 'O'"
+        );
+    }
+
+    #[test]
+    fn write_and_parse_back() {
+        let cells = vec![Coord(5, 5), Coord(7, 5), Coord(6, 7)];
+
+        let mut out = Vec::new();
+        PlaintextWriter::new()
+            .name("Three cells")
+            .write(&cells, &mut out)
+            .unwrap();
+        let written = String::from_utf8(out).unwrap();
+
+        assert_eq!(written, "!Name: Three cells\nO.O\n\n.O\n");
+
+        let mut storage = FakeStorage { cells: vec![] };
+        parse(&written, &mut storage).unwrap();
+        assert_eq!(storage.cells, vec![Coord(0, 0), Coord(2, 0), Coord(1, 2)]);
+    }
+}
+
+/// Header of a plaintext (`.cells`) file, with the size of the pattern
+#[derive(Debug)]
+pub struct PlaintextLife {
+    pub x: common::Int,
+    pub y: common::Int,
+    pub name: Option<String>,
+    pub comments: Vec<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParsePlaintextError {
+    pub line: usize,
+    reason: String,
+}
+
+impl fmt::Display for ParsePlaintextError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParsePlaintextError {}
+
+/// Parses the plaintext format: `!` starts comment lines, `.` is a dead cell
+/// and `O` a live one. Documented at
+/// http://www.conwaylife.com/wiki/Plaintext
+pub fn parse(
+    content: &str,
+    storage: &mut dyn LifePlaceMaker,
+) -> Result<PlaintextLife, ParsePlaintextError> {
+    let mut life = PlaintextLife {
+        x: 0,
+        y: 0,
+        name: None,
+        comments: vec![],
+    };
+
+    let mut y: common::Int = 0;
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim_end();
+
+        if let Some(comment) = line.strip_prefix('!') {
+            match comment.strip_prefix("Name:") {
+                Some(name) => life.name = Some(name.trim().to_string()),
+                None => life.comments.push(comment.trim().to_string()),
+            }
+
+            continue;
+        }
+
+        for (x, c) in line.chars().enumerate() {
+            match c {
+                '.' => {}
+                'O' | 'o' | '*' => storage.make_cell_alive(Coord(x as common::Int, y)),
+                c => {
+                    return Err(ParsePlaintextError {
+                        line: i + 1,
+                        reason: format!("unexpected '{}', cells must be '.' or 'O'", c),
+                    })
+                }
+            }
+        }
+
+        life.x = life.x.max(line.chars().count() as common::Int);
+        y += 1;
+        life.y = y;
+    }
+
+    Ok(life)
+}
+
+/// Writes live cells as a plaintext file, with the top-left corner of the
+/// bounding box of the cells as the pattern origin. Trailing dead cells of
+/// each line are left out
+pub struct PlaintextWriter {
+    name: Option<String>,
+    comments: Vec<String>,
+}
+
+impl Default for PlaintextWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PlaintextWriter {
+    pub fn new() -> Self {
+        PlaintextWriter {
+            name: None,
+            comments: vec![],
+        }
+    }
+
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    pub fn write(&self, cells: &[Coord], out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        if let Some(name) = &self.name {
+            writeln!(out, "!Name: {}", name)?;
+        }
+
+        for comment in &self.comments {
+            writeln!(out, "!{}", comment)?;
+        }
+
+        let mut cells = cells.to_vec();
+        cells.sort_unstable_by_key(|c| (c.1, c.0));
+        cells.dedup();
+
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.first().map_or(0, |c| c.1);
+
+        let mut line = String::new();
+        let mut y = min_y;
+
+        for c in &cells {
+            while c.1 != y {
+                writeln!(out, "{}", line)?;
+                line.clear();
+                y += 1;
+            }
+
+            let column = (c.0 - min_x) as usize;
+            line.extend(std::iter::repeat_n('.', column - line.len()));
+            line.push('O');
+        }
+
+        if !cells.is_empty() {
+            writeln!(out, "{}", line)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+use crate::world::Coord;
+use std::io;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(generation: usize, population: usize) -> GenerationStats {
+        GenerationStats {
+            generation,
+            population,
+            births: 0,
+            deaths: 0,
+            bounding_box: None,
+        }
+    }
+
+    fn recorder() -> PopulationRecorder {
+        let mut recorder = PopulationRecorder::new();
+        recorder.record(GenerationStats {
+            bounding_box: Some((Coord(0, 0), Coord(0, 2))),
+            ..stats(0, 3)
+        });
+        recorder.record(GenerationStats {
+            births: 2,
+            deaths: 2,
+            bounding_box: Some((Coord(-1, 1), Coord(1, 1))),
+            ..stats(1, 3)
+        });
+        recorder
+    }
+
+    #[test]
+    fn export_csv() {
+        let mut out = Vec::new();
+        recorder().write_csv(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "generation,population,births,deaths,min_x,min_y,max_x,max_y\n\
+             0,3,0,0,0,0,0,2\n\
+             1,3,2,2,-1,1,1,1\n"
+        );
+    }
+
+    #[test]
+    fn export_json() {
+        let mut recorder = PopulationRecorder::new();
+        recorder.record(GenerationStats {
+            deaths: 1,
+            ..stats(4, 0)
+        });
+
+        let mut out = Vec::new();
+        recorder.write_json(&mut out).unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[\n  {\"generation\": 4, \"population\": 0, \"births\": 0, \"deaths\": 1, \
+             \"bounding_box\": null}\n]\n"
+        );
+    }
+
+    #[test]
+    fn sparkline_of_growing_population() {
+        let mut recorder = PopulationRecorder::new();
+
+        for population in 0..8 {
+            recorder.record(stats(population, population));
+        }
+
+        assert_eq!(recorder.sparkline(8), "▁▂▃▄▅▆▇█");
+        assert_eq!(recorder.sparkline(2), "▁█");
+    }
+}
+
+/// Statistics of a single generation
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationStats {
+    pub generation: usize,
+    pub population: usize,
+    pub births: usize,
+    pub deaths: usize,
+    /// Top-left and bottom-right live cells, inclusive
+    pub bounding_box: Option<(Coord, Coord)>,
+}
+
+/// Keeps the population history of a `World`, one entry per generation
+#[derive(Debug, Default, Clone)]
+pub struct PopulationRecorder {
+    entries: Vec<GenerationStats>,
+}
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+impl PopulationRecorder {
+    pub fn new() -> Self {
+        PopulationRecorder { entries: vec![] }
+    }
+
+    pub fn record(&mut self, stats: GenerationStats) {
+        self.entries.push(stats);
+    }
+
+    pub fn entries(&self) -> &[GenerationStats] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops the entries after `generation`, as when going back to it
+    pub fn truncate(&mut self, generation: usize) {
+        self.entries.retain(|e| e.generation <= generation);
+    }
+
+    pub fn write_csv(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "generation,population,births,deaths,min_x,min_y,max_x,max_y"
+        )?;
+
+        for e in &self.entries {
+            write!(
+                out,
+                "{},{},{},{}",
+                e.generation, e.population, e.births, e.deaths
+            )?;
+
+            match e.bounding_box {
+                Some((min, max)) => writeln!(out, ",{},{},{},{}", min.0, min.1, max.0, max.1)?,
+                None => writeln!(out, ",,,,")?,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn write_json(&self, out: &mut dyn io::Write) -> io::Result<()> {
+        writeln!(out, "[")?;
+
+        for (i, e) in self.entries.iter().enumerate() {
+            write!(
+                out,
+                "  {{\"generation\": {}, \"population\": {}, \"births\": {}, \"deaths\": {}, ",
+                e.generation, e.population, e.births, e.deaths
+            )?;
+
+            match e.bounding_box {
+                Some((min, max)) => write!(
+                    out,
+                    "\"bounding_box\": [{}, {}, {}, {}]}}",
+                    min.0, min.1, max.0, max.1
+                )?,
+                None => write!(out, "\"bounding_box\": null}}")?,
+            }
+
+            writeln!(out, "{}", if i + 1 < self.entries.len() { "," } else { "" })?;
+        }
+
+        writeln!(out, "]")
+    }
+
+    /// Population of the last `width` generations, scaled to their minimum
+    /// and maximum
+    pub fn sparkline(&self, width: usize) -> String {
+        let start = self.entries.len().saturating_sub(width);
+        let entries = &self.entries[start..];
+
+        let min = entries.iter().map(|e| e.population).min().unwrap_or(0);
+        let max = entries.iter().map(|e| e.population).max().unwrap_or(0);
+
+        entries
+            .iter()
+            .map(|e| match max - min {
+                0 => SPARKS[0],
+                range => SPARKS[(e.population - min) * (SPARKS.len() - 1) / range],
+            })
+            .collect()
+    }
+}
@@ -25,6 +25,14 @@ mod tests {
         assert_eq!(storage.cells, vec![]);
     }
 
+    #[test]
+    fn parse_empty_body() {
+        let mut storage = FakeStorage { cells: vec![] };
+        let content = "x = 0, y = 0, rule = B3/S23\n!\n";
+        parse(content, &mut storage).unwrap();
+        assert_eq!(storage.cells, vec![]);
+    }
+
     #[test]
     fn parse_glider() {
         let content = r#"
@@ -68,34 +76,339 @@ obo$10bo5bo7bo$11bo3bo$12b2o!
         let parsed = parse(content, &mut storage).unwrap();
         assert_eq!(parsed.x, 36);
         assert_eq!(parsed.y, 9);
+        assert_eq!(parsed.name, Some("Gosper glider gun".to_string()));
+        assert_eq!(parsed.author, None);
+        assert_eq!(
+            parsed.comments,
+            vec![
+                "This was the first gun discovered.",
+                "As its name suggests, it was discovered by Bill Gosper."
+            ]
+        );
+
+        assert!(!storage.cells.is_empty());
+    }
+
+    #[derive(Debug)]
+    struct FakeStateStorage {
+        cells: Vec<(Coord, u8)>,
+    }
+
+    impl LifePlaceMaker for FakeStateStorage {
+        fn make_cell_alive(&mut self, coord: Coord) {
+            self.cells.push((coord, 1));
+        }
+
+        fn make_cell_state(&mut self, coord: Coord, state: u8) {
+            self.cells.push((coord, state));
+        }
+    }
+
+    #[test]
+    fn parse_rule() {
+        let mut storage = FakeStorage { cells: vec![] };
+        let parsed = parse("x = 1, y = 1, rule = B2/S/C3\no!", &mut storage).unwrap();
+        assert_eq!(parsed.rule, Some("B2/S/C3".to_string()));
+
+        let parsed = parse("x = 1, y = 1\no!", &mut storage).unwrap();
+        assert_eq!(parsed.rule, None);
+
+        let parsed = parse("x = 1, y = 1, rule = B2-a/S12\no!", &mut storage).unwrap();
+        assert_eq!(parsed.rule, Some("B2-a/S12".to_string()));
+
+        let parsed = parse("x = 1, y = 1, rule = B3/S23:K100*,80\no!", &mut storage).unwrap();
+        assert_eq!(parsed.rule, Some("B3/S23:K100*,80".to_string()));
+
+        let content = "x = 1, y = 1, rule = R5,C0,M1,S34..58,B34..45,NM\no!";
+        let parsed = parse(content, &mut storage).unwrap();
+        assert_eq!(parsed.rule, Some("R5,C0,M1,S34..58,B34..45,NM".to_string()));
+    }
+
+    #[test]
+    fn parse_golly_extended_header() {
+        let content = "#CXRLE Pos=-12,-7 Gen=1500\n#C a domino\nx = 2, y = 2\no$bo!";
+        let mut storage = FakeStorage { cells: vec![] };
+        let parsed = parse(content, &mut storage).unwrap();
+
+        assert_eq!(parsed.position, Some(Coord(-12, -7)));
+        assert_eq!(parsed.generation, Some(1500));
+        assert_eq!(parsed.comments, vec!["a domino"]);
+        assert_eq!(storage.cells, vec![Coord(-12, -7), Coord(-11, -6)]);
+
+        let mut storage = FakeStorage { cells: vec![] };
+        let parsed = parse("#CXRLE Gen=3\nx = 1, y = 1\no!", &mut storage).unwrap();
+
+        assert_eq!(parsed.position, None);
+        assert_eq!(parsed.generation, Some(3));
+        assert_eq!(storage.cells, vec![Coord(0, 0)]);
+    }
+
+    #[test]
+    fn parse_multi_state() {
+        use crate::world::Coord;
+
+        let content = "x = 4, y = 2, rule = B2/S/C3\n.AB$2.pA!";
+        let mut storage = FakeStateStorage { cells: vec![] };
+        parse(content, &mut storage).unwrap();
+
+        assert_eq!(
+            storage.cells,
+            vec![(Coord(1, 0), 1), (Coord(2, 0), 2), (Coord(2, 1), 25)]
+        );
+
+        let mut alive_only = FakeStorage { cells: vec![] };
+        parse(content, &mut alive_only).unwrap();
+        assert_eq!(alive_only.cells, vec![Coord(1, 0)]);
+    }
+
+    #[test]
+    fn parse_states_up_to_255() {
+        let content = "x = 2, y = 1, rule = B2/S/C256
+yOxX!";
+
+        let mut storage = FakeStateStorage { cells: vec![] };
+        parse(content, &mut storage).unwrap();
+        assert_eq!(storage.cells, vec![(Coord(0, 0), 255), (Coord(1, 0), 240)]);
+
+        let mut storage = FakeStateStorage { cells: vec![] };
+        parse_reader(&mut content.as_bytes(), &mut storage).unwrap();
+        assert_eq!(storage.cells, vec![(Coord(0, 0), 255), (Coord(1, 0), 240)]);
+
+        let content = "x = 2, y = 2
+o$2.yX!";
+
+        let error = parse(content, &mut storage).unwrap_err();
+        assert_eq!(
+            error.line_col,
+            pest::error::LineColLocation::Span((2, 5), (2, 7))
+        );
+        assert!(error.to_string().contains("states go up to 255 (yO)"));
+
+        let error = parse_reader(&mut content.as_bytes(), &mut storage).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: states go up to 255 (yO)");
+    }
+
+    #[test]
+    fn parse_while_reading() {
+        let contents = [
+            "x = 0, y = 0\n",
+            "#N Glider\n#O Richard K. Guy\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n",
+            "#CXRLE Pos=-12,-7 Gen=1500\n#C a domino\n\nx=2,y=2\no$\nbo!garbage",
+            "x = 4, y = 3, rule = B2/S/C30\n.AB$2.pA2$\n12b3o!",
+        ];
+
+        for content in contents {
+            let mut expected = FakeStateStorage { cells: vec![] };
+            let parsed = parse(content, &mut expected).unwrap();
+
+            let mut storage = FakeStateStorage { cells: vec![] };
+            let read = parse_reader(&mut content.as_bytes(), &mut storage).unwrap();
+
+            assert_eq!(storage.cells, expected.cells);
+            assert_eq!(
+                (read.x, read.y, read.rule),
+                (parsed.x, parsed.y, parsed.rule)
+            );
+            assert_eq!((read.name, read.author), (parsed.name, parsed.author));
+            assert_eq!(read.comments, parsed.comments);
+            assert_eq!(read.position, parsed.position);
+            assert_eq!(read.generation, parsed.generation);
+        }
+    }
+
+    #[test]
+    fn parse_while_reading_errors() {
+        let mut storage = FakeStorage { cells: vec![] };
+
+        let error = parse_reader(&mut "#C no header\n".as_bytes(), &mut storage).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: missing header");
+
+        let error = parse_reader(&mut "x = 3, y\nbo!".as_bytes(), &mut storage).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "line 1: invalid header 'x = 3, y', expected 'x = <width>, y = <height>'"
+        );
 
-        assert!(storage.cells.len() > 0);
+        let content = "x = 3, y = 2\nbo$\n2bz!";
+        let error = parse_reader(&mut content.as_bytes(), &mut storage).unwrap_err();
+        assert_eq!(error.to_string(), "line 3: unexpected 'z'");
+    }
+
+    #[test]
+    fn parse_runs_at_once() {
+        struct RunStorage {
+            runs: Vec<(Coord, common::Int)>,
+        }
+
+        impl LifePlaceMaker for RunStorage {
+            fn make_cell_alive(&mut self, coord: Coord) {
+                self.runs.push((coord, 1));
+            }
+
+            fn make_run_alive(&mut self, start: Coord, len: common::Int) {
+                self.runs.push((start, len));
+            }
+        }
+
+        let content = "x = 12, y = 2\n3o2b7o$bo!";
+        let expected = vec![(Coord(0, 0), 3), (Coord(5, 0), 7), (Coord(1, 1), 1)];
+
+        let mut storage = RunStorage { runs: vec![] };
+        parse(content, &mut storage).unwrap();
+        assert_eq!(storage.runs, expected);
+
+        let mut storage = RunStorage { runs: vec![] };
+        parse_reader(&mut content.as_bytes(), &mut storage).unwrap();
+        assert_eq!(storage.runs, expected);
+
+        // by default, runs are placed cell by cell
+        let mut storage = FakeStorage { cells: vec![] };
+        storage.make_run_alive(Coord(-1, 2), 3);
+        assert_eq!(storage.cells, vec![Coord(-1, 2), Coord(0, 2), Coord(1, 2)]);
+    }
+
+    #[test]
+    fn write_multi_state() {
+        use crate::world::Coord;
+
+        let cells = vec![(Coord(1, 0), 1), (Coord(2, 0), 2), (Coord(2, 1), 25)];
+
+        let mut out = Vec::new();
+        LreWriter::new()
+            .rule("B2/S/C30")
+            .write_states(&cells, &mut out)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "x = 2, y = 2, rule = B2/S/C30\nAB$.pA!\n"
+        );
     }
 
     #[test]
     fn parse_body() {
         LreFile::parse(Rule::Body, "bo$2bo$3o\n3o!").unwrap();
     }
+
+    #[test]
+    fn write_glider() {
+        use crate::world::Coord;
+
+        let cells = vec![
+            Coord(12, 11),
+            Coord(10, 12),
+            Coord(11, 10),
+            Coord(11, 12),
+            Coord(12, 12),
+        ];
+
+        let mut out = Vec::new();
+        LreWriter::new()
+            .comment("a glider")
+            .write(&cells, &mut out)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "#C a glider\nx = 3, y = 3, rule = B3/S23\nbo$2bo$3o!\n"
+        );
+    }
+
+    #[test]
+    fn write_generation_and_position() {
+        use crate::world::Coord;
+
+        let cells = vec![Coord(-3, 7), Coord(-2, 8)];
+
+        let mut out = Vec::new();
+        LreWriter::new()
+            .generation(120)
+            .name("diagonal")
+            .write(&cells, &mut out)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "#CXRLE Pos=-3,7 Gen=120\n#N diagonal\nx = 2, y = 2, rule = B3/S23\no$bo!\n"
+        );
+    }
+
+    #[test]
+    fn write_empty_rows_and_wrapping() {
+        use crate::world::Coord;
+
+        let cells = (0..40)
+            .map(|i| Coord(i * 2, 0))
+            .chain(std::iter::once(Coord(0, 3)))
+            .collect::<Vec<_>>();
+
+        let mut out = Vec::new();
+        LreWriter::new().write(&cells, &mut out).unwrap();
+        let written = String::from_utf8(out).unwrap();
+
+        assert!(written.lines().all(|line| line.len() <= 70));
+
+        let mut storage = FakeStorage { cells: vec![] };
+        let parsed = parse(&written, &mut storage).unwrap();
+        assert_eq!(parsed.x, 79);
+        assert_eq!(parsed.y, 4);
+
+        let mut expected = cells.clone();
+        expected.sort_by_key(|c| (c.1, c.0));
+        assert_eq!(storage.cells, expected);
+    }
 }
 
 pub trait LifePlaceMaker {
     fn make_cell_alive(&mut self, coord: Coord);
+
+    /// Places `len` alive cells in a row, from `start` to the right, as RLE
+    /// runs do. Cell by cell by default
+    fn make_run_alive(&mut self, start: Coord, len: common::Int) {
+        for x in 0..len {
+            self.make_cell_alive(Coord(start.0 + x, start.1));
+        }
+    }
+
+    /// Places a cell of a multi-state pattern. Only alive cells (state 1) are
+    /// placed by default
+    fn make_cell_state(&mut self, coord: Coord, state: u8) {
+        if state == 1 {
+            self.make_cell_alive(coord);
+        }
+    }
 }
 
 use crate::world::Coord;
 use pest::Parser;
 use pest_derive::*;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::BufRead;
 
 #[derive(Parser)]
 #[grammar = "rle.pest"]
 struct LreFile;
 
+#[derive(Debug)]
 pub struct LreLife {
-    x: common::Int,
-    y: common::Int,
+    pub x: common::Int,
+    pub y: common::Int,
+    pub rule: Option<String>,
+    /// From `#N` lines
+    pub name: Option<String>,
+    /// From `#O` lines
+    pub author: Option<String>,
+    /// From `#C` and `#c` lines
+    pub comments: Vec<String>,
+    /// Where the top-left corner of the pattern is, from a Golly `#CXRLE Pos=`
+    /// line. The cells are placed relative to it
+    pub position: Option<Coord>,
+    /// From a Golly `#CXRLE Gen=` line
+    pub generation: Option<usize>,
 }
 
-fn get_x_y(pair: pest::iterators::Pair<Rule>) -> (common::Int, common::Int) {
+fn get_header(pair: pest::iterators::Pair<Rule>) -> LreLife {
     let mut inner = pair.into_inner();
 
     let mut number_from_node = || {
@@ -106,27 +419,129 @@ fn get_x_y(pair: pest::iterators::Pair<Rule>) -> (common::Int, common::Int) {
     let x = number_from_node();
     let y = number_from_node();
 
-    (x, y)
+    let rule = inner.next().map(|node| node.as_str().to_string());
+
+    LreLife {
+        x,
+        y,
+        rule,
+        name: None,
+        author: None,
+        comments: vec![],
+        position: None,
+        generation: None,
+    }
 }
 
-fn get_body_contents(node: pest::iterators::Pair<Rule>, storage: &mut LifePlaceMaker) {
-    let patterns = node.into_inner().next().unwrap().into_inner();
+/// Reads the `Pos=x,y` and `Gen=n` fields of a `#CXRLE` line. Other fields,
+/// and values that can't be read, are ignored
+fn read_cxrle(fields: &str, life: &mut LreLife) {
+    for field in fields.split_whitespace() {
+        match field.split_once('=') {
+            Some(("Pos", position)) => {
+                life.position = position
+                    .split_once(',')
+                    .and_then(|(x, y)| Some(Coord(x.trim().parse().ok()?, y.trim().parse().ok()?)))
+            }
+            Some(("Gen", generation)) => life.generation = generation.parse().ok(),
+            _ => {}
+        }
+    }
+}
+
+/// Keeps the name, author and comments of the pattern, ignoring other lines
+fn read_meta(meta: &str, life: &mut LreLife) {
+    if let Some(fields) = meta.strip_prefix("#CXRLE") {
+        read_cxrle(fields, life);
+        return;
+    }
+
+    let content = meta.get(2..).unwrap_or("").trim().to_string();
 
-    let mut line: common::Int = 0;
-    let mut column: common::Int = 0;
+    match meta.get(1..2) {
+        Some("N") => life.name = Some(content),
+        Some("O") => life.author = Some(content),
+        Some("C") | Some("c") => life.comments.push(content),
+        _ => {}
+    }
+}
+
+fn read_metas(metas: pest::iterators::Pair<Rule>, life: &mut LreLife) {
+    for meta in metas.into_inner() {
+        read_meta(meta.as_str(), life);
+    }
+}
+
+/// The state of a multi-state tag, from `A` (1) to `X` (24), or from `pA`
+/// (25) to `yO` (255). None for the tags past `yO`, like `yX` (264)
+fn tag_state(tag: &[u8]) -> Option<u8> {
+    let state = tag.iter().fold(0u16, |state, b| match b {
+        b'A'..=b'X' => state + (b - b'A' + 1) as u16,
+        _ => (b - b'p' + 1) as u16 * 24,
+    });
+
+    u8::try_from(state).ok()
+}
+
+const STATE_TOO_LARGE: &str = "states go up to 255 (yO)";
+
+fn get_state(tag: pest::iterators::Pair<Rule>) -> Result<u8, pest::error::Error<Rule>> {
+    let tag = tag.into_inner().next().unwrap();
+
+    match tag.as_rule() {
+        Rule::DeadTag => Ok(0),
+        Rule::AliveTag => Ok(1),
+        Rule::StateTag => tag_state(tag.as_str().as_bytes()).ok_or_else(|| {
+            let message = STATE_TOO_LARGE.to_string();
+            pest::error::Error::new_from_span(
+                pest::error::ErrorVariant::CustomError { message },
+                tag.as_span(),
+            )
+        }),
+        _ => unreachable!(),
+    }
+}
+
+fn place_run(storage: &mut dyn LifePlaceMaker, start: Coord, len: common::Int, state: u8) {
+    match state {
+        0 => {}
+        1 => storage.make_run_alive(start, len),
+        state => {
+            for x in 0..len {
+                storage.make_cell_state(Coord(start.0 + x, start.1), state);
+            }
+        }
+    }
+}
+
+fn get_body_contents(
+    node: pest::iterators::Pair<Rule>,
+    origin: Coord,
+    storage: &mut dyn LifePlaceMaker,
+) -> Result<(), pest::error::Error<Rule>> {
+    let patterns = match node.into_inner().next() {
+        Some(patterns) => patterns.into_inner(),
+        None => return Ok(()),
+    };
+
+    let mut line: common::Int = origin.1;
+    let mut column: common::Int = origin.0;
 
     // TODO: refactor this loop and all those nested blocks to their own functions!
     for pattern in patterns {
         for pattern_type in pattern.into_inner() {
             match pattern_type.as_rule() {
                 Rule::EndOfLinePattern => {
-                    line += 1;
-                    column = 0;
+                    line += pattern_type
+                        .into_inner()
+                        .next()
+                        .map_or(1, |count| count.as_str().parse::<common::Int>().unwrap());
+                    column = origin.0;
                 }
 
                 Rule::DeadOrAlive => {
                     let mut run_count: common::Int = 1;
-                    let mut should_add = false;
+                    let mut state = 0;
 
                     for component in pattern_type.into_inner() {
                         match component.as_rule() {
@@ -134,23 +549,13 @@ fn get_body_contents(node: pest::iterators::Pair<Rule>, storage: &mut LifePlaceM
                                 run_count = component.as_str().parse::<common::Int>().unwrap();
                             }
                             Rule::Tag => {
-                                if component.into_inner().next().unwrap().as_rule()
-                                    == Rule::AliveTag
-                                {
-                                    should_add = true;
-                                }
+                                state = get_state(component)?;
                             }
                             _ => unreachable!(),
                         }
                     }
 
-                    if should_add {
-                        for c in 0..run_count {
-                            let column = column + c;
-                            storage.make_cell_alive(Coord(column, line));
-                        }
-                    }
-
+                    place_run(storage, Coord(column, line), run_count, state);
                     column += run_count;
                 }
 
@@ -158,26 +563,391 @@ fn get_body_contents(node: pest::iterators::Pair<Rule>, storage: &mut LifePlaceM
             }
         }
     }
+
+    Ok(())
 }
 
 pub fn parse(
     content: &str,
-    storage: &mut LifePlaceMaker,
+    storage: &mut dyn LifePlaceMaker,
 ) -> Result<LreLife, pest::error::Error<Rule>> {
     let p = LreFile::parse(Rule::File, content)?.next().unwrap();
 
     // TODO: define this in terms of for/match instead of manually unwrapping
     let mut inner = p.into_inner();
-    inner.next();
+    let metas = inner.next().unwrap();
     let node = inner.next();
-    let size = get_x_y(node.unwrap());
+    let mut life = get_header(node.unwrap());
+    read_metas(metas, &mut life);
 
     if let Some(body) = inner.next() {
-        get_body_contents(body, storage);
+        let origin = life.position.unwrap_or(Coord(0, 0));
+        get_body_contents(body, origin, storage)?;
     }
 
-    Ok(LreLife {
-        x: size.0,
-        y: size.1,
-    })
+    Ok(life)
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseRleError {
+    pub line: usize,
+    reason: String,
+}
+
+impl fmt::Display for ParseRleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
+
+impl std::error::Error for ParseRleError {}
+
+/// Reads `x = <width>, y = <height>` and the optional `rule = <rule>`
+fn read_header(header: &str, life: &mut LreLife) -> Option<()> {
+    let header = header
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+
+    let (x, rest) = header.strip_prefix("x=")?.split_once(",y=")?;
+
+    let (y, rule) = match rest.split_once(",rule=") {
+        Some((y, rule)) => (y, Some(rule.to_string())),
+        None => (rest, None),
+    };
+
+    life.x = x.parse().ok()?;
+    life.y = y.parse().ok()?;
+    life.rule = rule;
+
+    Some(())
+}
+
+/// Parses an RLE file as it's read, without holding all of it in memory, so
+/// that huge patterns can be loaded. Unlike `parse`, it doesn't
+/// validate the file beyond what it needs to place the cells: unknown `#`
+/// lines and anything after the final `!` are ignored
+pub fn parse_reader(
+    input: &mut dyn BufRead,
+    storage: &mut dyn LifePlaceMaker,
+) -> Result<LreLife, ParseRleError> {
+    let mut life = LreLife {
+        x: 0,
+        y: 0,
+        rule: None,
+        name: None,
+        author: None,
+        comments: vec![],
+        position: None,
+        generation: None,
+    };
+
+    let mut line_number = 0;
+    let error = |line: usize, reason: String| ParseRleError { line, reason };
+
+    let mut text = String::new();
+
+    loop {
+        text.clear();
+        line_number += 1;
+
+        match input.read_line(&mut text) {
+            Ok(0) => return Err(error(line_number, "missing header".to_string())),
+            Ok(_) => {}
+            Err(e) => return Err(error(line_number, e.to_string())),
+        }
+
+        let line = text.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            read_meta(line, &mut life);
+            continue;
+        }
+
+        if read_header(line, &mut life).is_none() {
+            let reason = format!(
+                "invalid header '{}', expected 'x = <width>, y = <height>'",
+                line
+            );
+            return Err(error(line_number, reason));
+        }
+
+        break;
+    }
+
+    let origin = life.position.unwrap_or(Coord(0, 0));
+    let (mut column, mut row) = (origin.0, origin.1);
+    let mut run_count: Option<common::Int> = None;
+    let mut prefix: Option<u8> = None;
+
+    line_number += 1;
+
+    loop {
+        let buffer = match input.fill_buf() {
+            Ok(buffer) => buffer,
+            Err(e) => return Err(error(line_number, e.to_string())),
+        };
+
+        if buffer.is_empty() {
+            break;
+        }
+
+        for &b in buffer {
+            if prefix.is_some() && !b.is_ascii_uppercase() {
+                let reason = "expected a state from 'A' to 'X' after its prefix".to_string();
+                return Err(error(line_number, reason));
+            }
+
+            match b {
+                b'0'..=b'9' => {
+                    run_count = run_count
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|count| count.checked_add((b - b'0') as common::Int));
+
+                    if run_count.is_none() {
+                        return Err(error(line_number, "run count too large".to_string()));
+                    }
+                }
+                b'b' | b'.' | b'o' | b'A'..=b'X' => {
+                    let state = match b {
+                        b'b' | b'.' => 0,
+                        b'o' => 1,
+                        letter => {
+                            let state = match prefix.take() {
+                                Some(prefix) => tag_state(&[prefix, letter]),
+                                None => tag_state(&[letter]),
+                            };
+
+                            match state {
+                                Some(state) => state,
+                                None => {
+                                    return Err(error(line_number, STATE_TOO_LARGE.to_string()))
+                                }
+                            }
+                        }
+                    };
+
+                    let count = run_count.take().unwrap_or(1);
+
+                    place_run(storage, Coord(column, row), count, state);
+                    column += count;
+                }
+                b'p'..=b'y' => prefix = Some(b),
+                b'$' => {
+                    row += run_count.take().unwrap_or(1);
+                    column = origin.0;
+                }
+                b'!' => return Ok(life),
+                b'\n' => line_number += 1,
+                b' ' | b'\t' | b'\r' => {}
+                b => {
+                    let reason = format!("unexpected '{}'", b as char);
+                    return Err(error(line_number, reason));
+                }
+            }
+        }
+
+        let len = buffer.len();
+        input.consume(len);
+    }
+
+    Ok(life)
+}
+
+/// Writes live cells as an RLE file, with the top-left corner of the
+/// bounding box of the cells as the pattern origin.
+pub struct LreWriter {
+    generation: Option<usize>,
+    name: Option<String>,
+    comments: Vec<String>,
+    rule: String,
+}
+
+const LINE_LENGTH: usize = 70;
+
+impl Default for LreWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LreWriter {
+    pub fn new() -> Self {
+        LreWriter {
+            generation: None,
+            name: None,
+            comments: vec![],
+            rule: "B3/S23".to_string(),
+        }
+    }
+
+    /// Records the generation, and the position of the pattern, in a Golly
+    /// `#CXRLE` line
+    pub fn generation(&mut self, generation: usize) -> &mut Self {
+        self.generation = Some(generation);
+        self
+    }
+
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn comment(&mut self, comment: &str) -> &mut Self {
+        self.comments.push(comment.to_string());
+        self
+    }
+
+    pub fn rule(&mut self, rule: &str) -> &mut Self {
+        self.rule = rule.to_string();
+        self
+    }
+
+    pub fn write(&self, cells: &[Coord], out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        let cells = cells.iter().map(|c| (*c, 1)).collect::<Vec<_>>();
+        self.write_states(&cells, out)
+    }
+
+    /// Writes cells of a multi-state pattern. Multi-state tags are only used
+    /// if some cell is in a state other than alive
+    pub fn write_states(
+        &self,
+        cells: &[(Coord, u8)],
+        out: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        let mut cells = cells.to_vec();
+        cells.sort_unstable_by_key(|(c, _)| (c.1, c.0));
+        cells.dedup_by_key(|(c, _)| *c);
+
+        let multi_state = cells.iter().any(|(_, state)| *state > 1);
+
+        let min_x = cells.iter().map(|(c, _)| c.0).min().unwrap_or(0);
+        let max_x = cells.iter().map(|(c, _)| c.0).max().unwrap_or(-1);
+        let min_y = cells.first().map(|(c, _)| c.1).unwrap_or(0);
+        let max_y = cells.last().map(|(c, _)| c.1).unwrap_or(-1);
+
+        if let Some(generation) = self.generation {
+            writeln!(out, "#CXRLE Pos={},{} Gen={}", min_x, min_y, generation)?;
+        }
+
+        if let Some(name) = &self.name {
+            writeln!(out, "#N {}", name)?;
+        }
+
+        for comment in &self.comments {
+            writeln!(out, "#C {}", comment)?;
+        }
+
+        writeln!(
+            out,
+            "x = {}, y = {}, rule = {}",
+            max_x - min_x + 1,
+            max_y - min_y + 1,
+            self.rule
+        )?;
+
+        let mut body = BodyWriter::new(out);
+
+        let mut line = min_y;
+        let mut column = min_x;
+
+        for (c, state) in &cells {
+            if c.1 != line {
+                body.run(c.1 - line, "$")?;
+                line = c.1;
+                column = min_x;
+            }
+
+            if c.0 != column {
+                body.run(c.0 - column, &state_tag(0, multi_state))?;
+            }
+
+            body.run(1, &state_tag(*state, multi_state))?;
+            column = c.0 + 1;
+        }
+
+        body.finish()
+    }
+}
+
+fn state_tag(state: u8, multi_state: bool) -> String {
+    match (state, multi_state) {
+        (0, false) => "b".to_string(),
+        (_, false) => "o".to_string(),
+        (0, true) => ".".to_string(),
+        (s, true) if s <= 24 => ((b'A' + s - 1) as char).to_string(),
+        (s, true) => format!(
+            "{}{}",
+            (b'p' + (s - 1) / 24 - 1) as char,
+            (b'A' + (s - 1) % 24) as char
+        ),
+    }
+}
+
+/// Accumulates RLE tokens, merging runs of the same tag and wrapping lines
+/// so that no token is split across them.
+struct BodyWriter<'a> {
+    out: &'a mut dyn std::io::Write,
+    line_length: usize,
+    pending: Option<(common::Int, String)>,
+}
+
+impl<'a> BodyWriter<'a> {
+    fn new(out: &'a mut dyn std::io::Write) -> Self {
+        BodyWriter {
+            out,
+            line_length: 0,
+            pending: None,
+        }
+    }
+
+    fn run(&mut self, count: common::Int, tag: &str) -> std::io::Result<()> {
+        match self.pending.as_mut() {
+            Some((pending_count, pending_tag)) if pending_tag == tag => {
+                *pending_count += count;
+                Ok(())
+            }
+            _ => {
+                self.flush()?;
+                self.pending = Some((count, tag.to_string()));
+                Ok(())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if let Some((count, tag)) = self.pending.take() {
+            let token = match count {
+                1 => tag,
+                _ => format!("{}{}", count, tag),
+            };
+
+            self.token(&token)?;
+        }
+
+        Ok(())
+    }
+
+    fn token(&mut self, token: &str) -> std::io::Result<()> {
+        if self.line_length + token.len() > LINE_LENGTH {
+            writeln!(self.out)?;
+            self.line_length = 0;
+        }
+
+        write!(self.out, "{}", token)?;
+        self.line_length += token.len();
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        self.flush()?;
+        self.token("!")?;
+        writeln!(self.out)
+    }
 }
@@ -0,0 +1,1133 @@
+use crate::common;
+use crate::topology::{BoundedGrid, Topology};
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts for which every configuration of neighboors is set
+    fn counts(table: &[bool]) -> Vec<u32> {
+        (0..=8)
+            .filter(|n| {
+                (0..256)
+                    .filter(|mask: &usize| mask.count_ones() == *n)
+                    .all(|mask| table[mask])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parse_life() {
+        let rule = "B3/S23".parse::<Rule>().unwrap();
+        assert_eq!(rule, Rule::default());
+        assert_eq!(rule.states(), 2);
+        assert_eq!(counts(&rule.birth), vec![3]);
+        assert_eq!(counts(&rule.survival), vec![2, 3]);
+    }
+
+    #[test]
+    fn parse_lowercase_and_survival_first() {
+        assert_eq!("b3/s23".parse::<Rule>().unwrap(), Rule::default());
+        assert_eq!("S23/B3".parse::<Rule>().unwrap(), Rule::default());
+        assert_eq!("23/3".parse::<Rule>().unwrap(), Rule::default());
+    }
+
+    #[test]
+    fn parse_generations() {
+        let brians_brain = "B2/S/C3".parse::<Rule>().unwrap();
+        assert_eq!(brians_brain.states(), 3);
+        assert_eq!(counts(&brians_brain.birth), vec![2]);
+        assert_eq!(counts(&brians_brain.survival), Vec::<u32>::new());
+
+        assert_eq!("/2/3".parse::<Rule>().unwrap(), brians_brain);
+        assert_eq!("B2/S/G3".parse::<Rule>().unwrap(), brians_brain);
+
+        let star_wars = "345/2/4".parse::<Rule>().unwrap();
+        assert_eq!(star_wars.states(), 4);
+        assert_eq!(star_wars.to_string(), "B2/S345/C4");
+    }
+
+    #[test]
+    fn parse_invalid_rules() {
+        assert!("B9/S23".parse::<Rule>().is_err());
+        assert!("B3/S23/C1".parse::<Rule>().is_err());
+        assert!("B3/X23".parse::<Rule>().is_err());
+        assert!("".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn display_canonical_form() {
+        assert_eq!(Rule::default().to_string(), "B3/S23");
+        assert_eq!("/2/3".parse::<Rule>().unwrap().to_string(), "B2/S/C3");
+    }
+
+    #[test]
+    fn generations_cells_decay() {
+        let rule = "B2/S/C4".parse::<Rule>().unwrap();
+
+        assert_eq!(rule.mutate(CellState::Alive, 0b111), CellState::Dying(2));
+        assert_eq!(rule.mutate(CellState::Dying(2), 0b11), CellState::Dying(3));
+        assert_eq!(rule.mutate(CellState::Dying(3), 0b11), CellState::Dead);
+        assert_eq!(rule.mutate(CellState::Dead, 0b11), CellState::Alive);
+    }
+
+    #[test]
+    fn hensel_letters_cover_all_configurations() {
+        for count in 1..=7 {
+            let letters = letters(count);
+            let mut masks = letters
+                .chars()
+                .flat_map(|letter| configurations(count, letter).unwrap())
+                .collect::<Vec<_>>();
+
+            masks.sort_unstable();
+            masks.dedup();
+
+            let expected = (0..=255u8)
+                .filter(|mask| mask.count_ones() == count)
+                .collect::<Vec<_>>();
+
+            assert_eq!(masks, expected);
+        }
+    }
+
+    #[test]
+    fn parse_isotropic() {
+        // Neighboors: bit 0 is north-west, then clockwise
+        let rule = "B2-a/S12".parse::<Rule>().unwrap();
+
+        // 2a: north-west and north
+        assert!(!rule.birth[0b0000_0011]);
+        // 2c: north-west and north-east
+        assert!(rule.birth[0b0000_0101]);
+        // 2i: north and south
+        assert!(rule.birth[0b0010_0010]);
+        assert!(rule.survival[0b0000_0001]);
+        assert!(rule.survival[0b1000_0010]);
+        assert!(!rule.survival[0b0000_0111]);
+
+        let rule = "B2ce3aeijkqr/S12".parse::<Rule>().unwrap();
+        assert!(rule.birth[0b0000_0101]);
+        assert!(!rule.birth[0b0000_0011]);
+        // 3c: three corners
+        assert!(!rule.birth[0b0001_0101]);
+        // 3e: three edges
+        assert!(rule.birth[0b0010_1010]);
+    }
+
+    #[test]
+    fn totalistic_rule_written_as_isotropic() {
+        let rule = "B3/S2ceaikn3ceaiknjqry".parse::<Rule>().unwrap();
+        assert_eq!(rule, Rule::default());
+        assert!(rule.is_totalistic());
+    }
+
+    #[test]
+    fn isotropic_canonical_form() {
+        let rule = "B3/S23-a4i".parse::<Rule>().unwrap();
+        assert!(!rule.is_totalistic());
+        assert_eq!(rule.to_string(), "B3/S23-a4i");
+
+        let rule = "B2ce3aeijkqr/S12/C3".parse::<Rule>().unwrap();
+        assert_eq!(rule.to_string(), "B2ce3-cny/S12/C3");
+    }
+
+    #[test]
+    fn parse_neighboorhood_suffixes() {
+        let rule = "B2/S013V".parse::<Rule>().unwrap();
+        assert_eq!(rule.neighboorhood(), &Neighboorhood::VonNeumann);
+        assert_eq!(rule.mutate(CellState::Dead, 0b1010), CellState::Alive);
+        assert_eq!(rule.mutate(CellState::Alive, 0b0110), CellState::Dead);
+        assert_eq!(rule.to_string(), "B2/S013V");
+
+        let rule = "b2/s34h".parse::<Rule>().unwrap();
+        assert_eq!(rule.neighboorhood(), &Neighboorhood::Hexagonal);
+        assert_eq!(rule.to_string(), "B2/S34H");
+
+        let rule = "B2/S/C3H".parse::<Rule>().unwrap();
+        assert_eq!(rule.states(), 3);
+        assert_eq!(rule.to_string(), "B2/S/C3H");
+
+        assert!("B5/S23V".parse::<Rule>().is_err());
+        assert!("B2a/S23H".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parse_larger_than_life() {
+        let rule = "R5,C0,M1,S34..58,B34..45,NM".parse::<Rule>().unwrap();
+
+        assert_eq!(rule.neighboorhood().offsets().len(), 121);
+        assert_eq!(rule.states(), 2);
+        assert_eq!(rule.mutate_count(CellState::Dead, 34), CellState::Alive);
+        assert_eq!(rule.mutate_count(CellState::Dead, 46), CellState::Dead);
+        assert_eq!(rule.mutate_count(CellState::Alive, 58), CellState::Alive);
+        assert_eq!(rule.mutate_count(CellState::Alive, 33), CellState::Dead);
+        assert_eq!(rule.to_string(), "R5,C0,M1,S34..58,B34..45,NM");
+    }
+
+    #[test]
+    fn parse_hrot() {
+        let rule = "R2,C3,S2-3,5,B3,NN".parse::<Rule>().unwrap();
+
+        assert_eq!(rule.neighboorhood().offsets().len(), 12);
+        assert_eq!(rule.states(), 3);
+        assert_eq!(rule.mutate_count(CellState::Alive, 4), CellState::Dying(2));
+        assert_eq!(rule.mutate_count(CellState::Alive, 5), CellState::Alive);
+        assert_eq!(rule.to_string(), "R2,C3,M0,S2-3,5,B3..3,NN");
+        assert_eq!(rule.to_string().parse::<Rule>(), Ok(rule));
+    }
+
+    #[test]
+    fn parse_invalid_larger_than_life() {
+        assert!("R0,C0,M0,S1..2,B1..2,NM".parse::<Rule>().is_err());
+        assert!("R1,C0,M0,S1..2,B1..9,NM".parse::<Rule>().is_err());
+        assert!("R1,C0,M0,S1..2,B0..2,NM".parse::<Rule>().is_err());
+        assert!("R1,C0,M0,S1..2,NM".parse::<Rule>().is_err());
+        assert!("R1,C0,M0,S1..2,B3,NX".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn parse_bounded_grids() {
+        let rule = "B3/S23:T100,80".parse::<Rule>().unwrap();
+        let grid = rule.bounded_grid().unwrap();
+
+        assert_eq!((grid.width, grid.height), (100, 80));
+        assert_eq!(rule.to_string(), "B3/S23:T100,80");
+
+        let rule = "B2/S34H:K20*,10".parse::<Rule>().unwrap();
+        assert_eq!(rule.neighboorhood(), &Neighboorhood::Hexagonal);
+        assert_eq!(rule.to_string(), "B2/S34H:K20*,10");
+
+        assert!("B3/S23:X10,10".parse::<Rule>().is_err());
+        assert!("B3/S23:T10,10:T10,10".parse::<Rule>().is_err());
+        assert!("R2,C0,M0,S1..2,B3..3,NM:T10,10".parse::<Rule>().is_err());
+    }
+
+    #[test]
+    fn b0_emulation_alternates() {
+        let rule = "B013/S0124".parse::<Rule>().unwrap();
+        let emulation = rule.b0_emulation().unwrap();
+
+        assert!(!emulation.stays_inverted);
+        assert!(!emulation.from_normal.has_b0());
+        assert!(!emulation.from_inverted.has_b0());
+        assert_eq!(emulation.from_normal.to_string(), "B245678/S35678");
+        assert_eq!(emulation.from_inverted.to_string(), "B4678/S578");
+    }
+
+    #[test]
+    fn b0_emulation_with_s8_stays_inverted() {
+        // AntiLife is Life on the inverted pattern
+        let rule = "B0123478/S01234678".parse::<Rule>().unwrap();
+        let emulation = rule.b0_emulation().unwrap();
+
+        assert!(emulation.stays_inverted);
+        assert_eq!(emulation.from_normal.to_string(), "B56/S5");
+        assert_eq!(emulation.from_inverted.to_string(), "B3/S23");
+
+        let rule = "B0/S8".parse::<Rule>().unwrap();
+        let emulation = rule.b0_emulation().unwrap();
+
+        assert!(emulation.stays_inverted);
+        assert_eq!(emulation.from_normal.to_string(), "B12345678/S01234567");
+        assert_eq!(emulation.from_inverted.to_string(), "B12345678/S01234567");
+
+        assert_eq!(Rule::default().b0_emulation(), None);
+    }
+
+    #[test]
+    fn b0_on_von_neumann_neighboorhood() {
+        let rule = "B02/S1V".parse::<Rule>().unwrap();
+        let emulation = rule.b0_emulation().unwrap();
+
+        assert_eq!(emulation.from_normal.to_string(), "B134/S0234V");
+        assert_eq!(emulation.from_inverted.to_string(), "B3/S24V");
+    }
+
+    #[test]
+    fn unsupported_b0_rules() {
+        assert!("B01/S2/C3".parse::<Rule>().is_err());
+        assert!("B01/S2:P10,10".parse::<Rule>().is_err());
+        assert!("B01/S2:T10,10".parse::<Rule>().is_ok());
+    }
+
+    #[test]
+    fn custom_neighboorhood_counts_neighboors() {
+        let knight = Neighboorhood::Custom(vec![(1, 2), (2, 1), (-1, 2), (-2, 1)]);
+        let rule = Rule::totalistic(knight, &[1], &[], 2);
+
+        assert_eq!(rule.mutate(CellState::Dead, 0b0100), CellState::Alive);
+        assert_eq!(rule.mutate(CellState::Dead, 0b0101), CellState::Dead);
+        assert_eq!(rule.neighboorhood().offsets().len(), 4);
+    }
+
+    #[test]
+    fn parse_invalid_isotropic_rules() {
+        assert!("B2z/S23".parse::<Rule>().is_err());
+        assert!("B-a/S23".parse::<Rule>().is_err());
+        assert!("23a/3".parse::<Rule>().is_err());
+    }
+}
+
+/// State of a single cell. Dying cells (only in Generations rules) count as
+/// dead for their neighboors, but can't be born until they finish decaying
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CellState {
+    Dead,
+    Alive,
+    Dying(u8),
+}
+
+/// Cells whose state is taken into account for the next state of a cell
+#[derive(Debug, Clone, PartialEq)]
+pub enum Neighboorhood {
+    Moore,
+    VonNeumann,
+    Hexagonal,
+    /// Arbitrary offsets, up to 64 of them
+    Custom(Vec<(common::Int, common::Int)>),
+    /// Larger than Life neighboorhood: every cell up to `range` away, in a
+    /// square or (with `von_neumann`) a diamond, including the cell itself
+    /// if `middle`. Built by `Neighboorhood::range`
+    Range {
+        range: common::Int,
+        von_neumann: bool,
+        middle: bool,
+        offsets: Vec<(common::Int, common::Int)>,
+    },
+}
+
+/// Largest range of Larger than Life rules
+pub const MAX_RANGE: common::Int = 100;
+
+/// Clockwise, starting from the north-west
+const MOORE: [(common::Int, common::Int); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+];
+
+const VON_NEUMANN: [(common::Int, common::Int); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Hexagonal grid skewed onto a square one, ignoring north-east and south-west
+const HEXAGONAL: [(common::Int, common::Int); 6] =
+    [(-1, -1), (0, -1), (1, 0), (1, 1), (0, 1), (-1, 0)];
+
+impl Neighboorhood {
+    pub fn range(range: common::Int, von_neumann: bool, middle: bool) -> Self {
+        assert!((1..=MAX_RANGE).contains(&range), "invalid range {}", range);
+
+        let offsets = (-range..=range)
+            .flat_map(|y| (-range..=range).map(move |x| (x, y)))
+            .filter(|(x, y)| !von_neumann || x.abs() + y.abs() <= range)
+            .filter(|offset| middle || *offset != (0, 0))
+            .collect();
+
+        Neighboorhood::Range {
+            range,
+            von_neumann,
+            middle,
+            offsets,
+        }
+    }
+
+    pub fn offsets(&self) -> &[(common::Int, common::Int)] {
+        match self {
+            Neighboorhood::Moore => &MOORE,
+            Neighboorhood::VonNeumann => &VON_NEUMANN,
+            Neighboorhood::Hexagonal => &HEXAGONAL,
+            Neighboorhood::Custom(offsets) => offsets,
+            Neighboorhood::Range { offsets, .. } => offsets,
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            Neighboorhood::VonNeumann => "V",
+            Neighboorhood::Hexagonal => "H",
+            _ => "",
+        }
+    }
+}
+
+/// Rule in the Life-like B/S or the Generations B/S/C form. On the Moore
+/// neighboorhood it can be isotropic non-totalistic (Hensel notation), on the
+/// others it's totalistic. Larger than Life rules use the LtL or HROT forms
+/// (`R5,C0,M1,S34..58,B34..45,NM`). Any of the other rules may be followed by
+/// a bounded grid, as in `B3/S23:T100,80`.
+///
+/// Transitions are indexed by the mask of live neighboors on the Moore
+/// neighboorhood (bit i set if the i-th offset is alive) and by their count
+/// on the others
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    neighboorhood: Neighboorhood,
+    birth: Vec<bool>,
+    survival: Vec<bool>,
+    states: u8,
+    grid: Option<BoundedGrid>,
+}
+
+/// Rules without B0 that simulate a rule with B0, see `Rule::b0_emulation`
+#[derive(Debug, Clone, PartialEq)]
+pub struct B0Emulation {
+    /// Evolves the pattern into its inverted next generation
+    pub from_normal: Rule,
+    /// Evolves the inverted pattern into the next generation, inverted again
+    /// if `stays_inverted`
+    pub from_inverted: Rule,
+    pub stays_inverted: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseRuleError {
+    rule: String,
+    reason: &'static str,
+}
+
+impl fmt::Display for ParseRuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid rule \"{}\": {}", self.rule, self.reason)
+    }
+}
+
+impl std::error::Error for ParseRuleError {}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule::totalistic(Neighboorhood::Moore, &[3], &[2, 3], 2)
+    }
+}
+
+/// Letters of each neighboor count in Hensel notation, in canonical order
+fn letters(count: u32) -> &'static str {
+    match count {
+        1 | 7 => "ce",
+        2 | 6 => "cekain",
+        3 | 5 => "cekainyqjr",
+        4 => "cekainyqjrtwz",
+        _ => "",
+    }
+}
+
+/// One configuration of each letter, the others are their rotations and
+/// reflections. Counts above 4 are the complements of the ones below
+const HENSEL: [&[(char, u8)]; 5] = [
+    &[],
+    &[('c', 0x01), ('e', 0x02)],
+    &[
+        ('c', 0x05),
+        ('e', 0x0a),
+        ('k', 0x09),
+        ('a', 0x03),
+        ('i', 0x22),
+        ('n', 0x11),
+    ],
+    &[
+        ('c', 0x15),
+        ('e', 0x2a),
+        ('k', 0x29),
+        ('a', 0x0e),
+        ('i', 0x07),
+        ('n', 0x0d),
+        ('y', 0x25),
+        ('q', 0x13),
+        ('j', 0x0b),
+        ('r', 0x23),
+    ],
+    &[
+        ('c', 0x55),
+        ('e', 0xaa),
+        ('k', 0x2d),
+        ('a', 0x0f),
+        ('i', 0x36),
+        ('n', 0x17),
+        ('y', 0x35),
+        ('q', 0x39),
+        ('j', 0x2b),
+        ('r', 0x2e),
+        ('t', 0x27),
+        ('w', 0x1b),
+        ('z', 0x33),
+    ],
+];
+
+/// All masks of live neighboors described by a count and a Hensel letter
+fn configurations(count: u32, letter: char) -> Option<Vec<u8>> {
+    let representative = |count: usize| {
+        HENSEL[count]
+            .iter()
+            .find(|(l, _)| *l == letter)
+            .map(|(_, mask)| *mask)
+    };
+
+    let mask = match count {
+        0..=4 => representative(count as usize)?,
+        5..=7 => !representative(8 - count as usize)?,
+        _ => return None,
+    };
+
+    let rotate = |m: u8| m.rotate_left(2);
+
+    // swaps west and east
+    let reflect = |m: u8| {
+        let swaps = [(0, 2), (7, 3), (6, 4)];
+        swaps.iter().fold(m & 0b0010_0010, |r, (a, b)| {
+            r | ((m >> a) & 1) << b | ((m >> b) & 1) << a
+        })
+    };
+
+    let mut masks = vec![];
+    let mut m = mask;
+
+    for _ in 0..4 {
+        masks.push(m);
+        masks.push(reflect(m));
+        m = rotate(m);
+    }
+
+    masks.sort_unstable();
+    masks.dedup();
+
+    Some(masks)
+}
+
+impl Rule {
+    /// Rule depending only on the number of live neighboors, on any
+    /// neighboorhood
+    pub fn totalistic(
+        neighboorhood: Neighboorhood,
+        birth: &[usize],
+        survival: &[usize],
+        states: u8,
+    ) -> Self {
+        let size = neighboorhood.offsets().len();
+
+        if let Neighboorhood::Custom(_) = neighboorhood {
+            assert!(size <= 64, "neighboorhoods are limited to 64 cells");
+        }
+
+        let table = |counts: &[usize]| match neighboorhood {
+            Neighboorhood::Moore => (0..256)
+                .map(|mask: usize| counts.contains(&(mask.count_ones() as usize)))
+                .collect(),
+            _ => (0..=size).map(|count| counts.contains(&count)).collect(),
+        };
+
+        Rule {
+            birth: table(birth),
+            survival: table(survival),
+            neighboorhood,
+            states,
+            grid: None,
+        }
+    }
+
+    /// Number of states, 2 for Life-like rules
+    pub fn states(&self) -> u8 {
+        self.states
+    }
+
+    pub fn neighboorhood(&self) -> &Neighboorhood {
+        &self.neighboorhood
+    }
+
+    /// The grid the rule runs on, unbounded if `None`
+    pub fn bounded_grid(&self) -> Option<&BoundedGrid> {
+        self.grid.as_ref()
+    }
+
+    /// Larger than Life rules can't run on bounded grids, and rules with B0
+    /// can't run on bounded planes
+    pub fn set_bounded_grid(&mut self, grid: Option<BoundedGrid>) {
+        assert!(
+            grid.is_none() || !matches!(self.neighboorhood, Neighboorhood::Range { .. }),
+            "Larger than Life rules don't support bounded grids"
+        );
+        assert!(
+            !self.birth[0] || grid.is_none_or(|grid| grid.topology != Topology::Plane),
+            "B0 rules don't support bounded planes"
+        );
+        self.grid = grid;
+    }
+
+    /// Whether dead cells without live neighboors are born
+    pub fn has_b0(&self) -> bool {
+        self.birth[0]
+    }
+
+    /// Index of the transition where every live neighboor is dead and the
+    /// other way around
+    fn complement(&self, neighboors: usize) -> usize {
+        match self.neighboorhood {
+            Neighboorhood::Moore => 0xff ^ neighboors,
+            _ => self.birth.len() - 1 - neighboors,
+        }
+    }
+
+    fn with_tables<F, G>(&self, birth: F, survival: G) -> Rule
+    where
+        F: Fn(usize) -> bool,
+        G: Fn(usize) -> bool,
+    {
+        Rule {
+            birth: (0..self.birth.len()).map(birth).collect(),
+            survival: (0..self.survival.len()).map(survival).collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Rules with B0 turn the infinite background alive, so they are
+    /// simulated on the inverted pattern (dead cells stored as the live ones)
+    /// every other generation, or every generation after the first one if
+    /// cells with every neighboor alive survive
+    pub fn b0_emulation(&self) -> Option<B0Emulation> {
+        if !self.has_b0() {
+            return None;
+        }
+
+        let all_alive = self.complement(0);
+        let stays_inverted = self.survival[all_alive];
+
+        let from_inverted = if stays_inverted {
+            self.with_tables(
+                |n| !self.survival[self.complement(n)],
+                |n| !self.birth[self.complement(n)],
+            )
+        } else {
+            self.with_tables(
+                |n| self.survival[self.complement(n)],
+                |n| self.birth[self.complement(n)],
+            )
+        };
+
+        Some(B0Emulation {
+            from_normal: self.with_tables(|n| !self.birth[n], |n| !self.survival[n]),
+            from_inverted,
+            stays_inverted,
+        })
+    }
+
+    /// Whether the transitions only depend on the number of live neighboors
+    pub fn is_totalistic(&self) -> bool {
+        if self.neighboorhood != Neighboorhood::Moore {
+            return true;
+        }
+
+        (0..256).all(|mask: usize| {
+            let same_count = (1 << mask.count_ones()) - 1;
+            self.birth[mask] == self.birth[same_count]
+                && self.survival[mask] == self.survival[same_count]
+        })
+    }
+
+    /// Next state of a cell, given the mask of its live neighboors (bit i set
+    /// if the i-th offset of the neighboorhood is alive)
+    pub fn mutate(&self, state: CellState, neighboors: u64) -> CellState {
+        match self.neighboorhood {
+            Neighboorhood::Moore => self.transition(state, neighboors as usize),
+            _ => self.transition(state, neighboors.count_ones() as usize),
+        }
+    }
+
+    /// Next state of a cell given its number of live neighboors, for
+    /// totalistic rules on neighboorhoods too large for a mask
+    pub fn mutate_count(&self, state: CellState, neighboors: usize) -> CellState {
+        debug_assert!(self.neighboorhood != Neighboorhood::Moore || neighboors == 0);
+        self.transition(state, neighboors)
+    }
+
+    fn transition(&self, state: CellState, neighboors: usize) -> CellState {
+        match state {
+            CellState::Dead if self.birth[neighboors] => CellState::Alive,
+            CellState::Dead => CellState::Dead,
+            CellState::Alive if self.survival[neighboors] => CellState::Alive,
+            CellState::Alive if self.states > 2 => CellState::Dying(2),
+            CellState::Alive => CellState::Dead,
+            CellState::Dying(s) if s + 1 < self.states => CellState::Dying(s + 1),
+            CellState::Dying(_) => CellState::Dead,
+        }
+    }
+}
+
+/// Parses neighboor counts, each optionally followed by Hensel letters (or
+/// by '-' and the letters to exclude)
+fn parse_counts(
+    rule: &str,
+    counts: &str,
+    neighboorhood: &Neighboorhood,
+) -> Result<Vec<bool>, ParseRuleError> {
+    let invalid = |reason| ParseRuleError {
+        rule: rule.to_string(),
+        reason,
+    };
+
+    if *neighboorhood != Neighboorhood::Moore {
+        let size = neighboorhood.offsets().len();
+        let mut table = vec![false; size + 1];
+
+        for d in counts.chars() {
+            match d.to_digit(10) {
+                Some(n) if n as usize <= size => table[n as usize] = true,
+                Some(_) => return Err(invalid("neighboor count larger than the neighboorhood")),
+                None => {
+                    return Err(invalid(
+                        "Hensel letters are only supported on the Moore neighboorhood",
+                    ))
+                }
+            }
+        }
+
+        return Ok(table);
+    }
+
+    let mut table = vec![false; 256];
+    let mut chars = counts.chars().peekable();
+
+    while let Some(d) = chars.next() {
+        let count = match d.to_digit(10) {
+            Some(n) if n <= 8 => n,
+            _ => return Err(invalid("neighboor counts must be digits from 0 to 8")),
+        };
+
+        let negated = chars.peek() == Some(&'-');
+
+        if negated {
+            chars.next();
+        }
+
+        let mut given = String::new();
+
+        while let Some(letter) = chars.peek().filter(|c| c.is_ascii_lowercase()) {
+            given.push(*letter);
+            chars.next();
+        }
+
+        if given.chars().any(|letter| !letters(count).contains(letter)) {
+            return Err(invalid("unknown Hensel letter for neighboor count"));
+        }
+
+        if negated && given.is_empty() {
+            return Err(invalid("'-' must be followed by Hensel letters"));
+        }
+
+        let included = letters(count)
+            .chars()
+            .filter(|letter| given.is_empty() || given.contains(*letter) != negated)
+            .flat_map(|letter| configurations(count, letter).unwrap())
+            .chain(
+                (0..=255u8).filter(|mask| letters(count).is_empty() && mask.count_ones() == count),
+            );
+
+        for mask in included {
+            table[mask as usize] = true;
+        }
+    }
+
+    Ok(table)
+}
+
+fn parse_states(rule: &str, states: &str) -> Result<u8, ParseRuleError> {
+    match states.parse::<u8>() {
+        Ok(states) if states >= 2 => Ok(states),
+        _ => Err(ParseRuleError {
+            rule: rule.to_string(),
+            reason: "number of states must be between 2 and 255",
+        }),
+    }
+}
+
+/// Parses a list of counts and count ranges (`34..58` or `34-58`) into a
+/// count-indexed table
+fn parse_count_ranges(
+    rule: &str,
+    ranges: &[&str],
+    size: usize,
+) -> Result<Vec<bool>, ParseRuleError> {
+    let invalid = |reason| ParseRuleError {
+        rule: rule.to_string(),
+        reason,
+    };
+
+    let number = |n: &str| n.parse::<usize>().map_err(|_| invalid("invalid count"));
+
+    let mut table = vec![false; size + 1];
+
+    for range in ranges.iter().filter(|range| !range.is_empty()) {
+        let (min, max) = match range.find("..").or_else(|| range.find('-')) {
+            Some(i) => (
+                number(&range[..i])?,
+                number(range[i..].trim_start_matches(['.', '-']))?,
+            ),
+            None => (number(range)?, number(range)?),
+        };
+
+        if min > max || max > size {
+            return Err(invalid("count range out of the neighboorhood"));
+        }
+
+        table[min..=max].iter_mut().for_each(|t| *t = true);
+    }
+
+    Ok(table)
+}
+
+/// Larger than Life rules, in the LtL form `R5,C0,M1,S34..58,B34..45,NM` or
+/// the HROT form `R2,C2,S5-9,11,B7-8,NN`
+fn parse_range_rule(rule: &str) -> Result<Rule, ParseRuleError> {
+    let invalid = |reason| ParseRuleError {
+        rule: rule.to_string(),
+        reason,
+    };
+
+    let mut range = None;
+    let mut states = 2;
+    let mut middle = false;
+    let mut von_neumann = false;
+    let mut birth = None;
+    let mut survival = None;
+
+    let mut parts = rule.split(',').peekable();
+
+    while let Some(part) = parts.next() {
+        let mut chars = part.chars();
+        let kind = chars.next().map(|c| c.to_ascii_uppercase());
+        let value = chars.as_str();
+
+        // counts of S and B go on until the next part starting with a letter
+        let mut counts = || {
+            let mut counts = vec![value];
+
+            while let Some(next) = parts.next_if(|p| p.starts_with(|c: char| c.is_ascii_digit())) {
+                counts.push(next);
+            }
+
+            counts
+        };
+
+        match kind {
+            Some('R') if range.is_none() => {
+                range = value
+                    .parse::<common::Int>()
+                    .ok()
+                    .filter(|r| (1..=MAX_RANGE).contains(r));
+
+                if range.is_none() {
+                    return Err(invalid("range must be between 1 and 100"));
+                }
+            }
+            Some('C') => {
+                states = match value {
+                    "0" | "1" => 2,
+                    _ => parse_states(rule, value)?,
+                }
+            }
+            Some('M') => middle = value == "1",
+            Some('N') => match value.to_ascii_uppercase().as_str() {
+                "M" => von_neumann = false,
+                "N" => von_neumann = true,
+                _ => return Err(invalid("unknown Larger than Life neighboorhood")),
+            },
+            Some('S') if survival.is_none() => survival = Some(counts()),
+            Some('B') if birth.is_none() => birth = Some(counts()),
+            _ => return Err(invalid("unknown rule part")),
+        }
+    }
+
+    let range = range.ok_or_else(|| invalid("range must be given"))?;
+    let neighboorhood = Neighboorhood::range(range, von_neumann, middle);
+    let size = neighboorhood.offsets().len();
+
+    match (birth, survival) {
+        (Some(birth), Some(survival)) => {
+            let birth = parse_count_ranges(rule, &birth, size)?;
+
+            if birth[0] {
+                return Err(invalid("births without live neighboors are not supported"));
+            }
+
+            Ok(Rule {
+                survival: parse_count_ranges(rule, &survival, size)?,
+                birth,
+                neighboorhood,
+                states,
+                grid: None,
+            })
+        }
+        _ => Err(invalid("both birth and survival must be given")),
+    }
+}
+
+/// Count ranges of a count-indexed table, in the LtL form if it's a single
+/// range and in the HROT form otherwise
+fn format_count_ranges(table: &[bool]) -> String {
+    let mut ranges = vec![];
+    let mut count = 0;
+
+    while count < table.len() {
+        if !table[count] {
+            count += 1;
+            continue;
+        }
+
+        let start = count;
+
+        while count + 1 < table.len() && table[count + 1] {
+            count += 1;
+        }
+
+        ranges.push((start, count));
+        count += 1;
+    }
+
+    match ranges.as_slice() {
+        [(min, max)] => format!("{}..{}", min, max),
+        ranges => ranges
+            .iter()
+            .map(|(min, max)| match min == max {
+                true => min.to_string(),
+                false => format!("{}-{}", min, max),
+            })
+            .collect::<Vec<_>>()
+            .join(","),
+    }
+}
+
+impl FromStr for Rule {
+    type Err = ParseRuleError;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| ParseRuleError {
+            rule: rule.to_string(),
+            reason,
+        };
+
+        if let Some((transitions, grid)) = rule.split_once(':') {
+            let grid = grid.parse::<BoundedGrid>().map_err(|e| invalid(e.reason))?;
+
+            let mut parsed = transitions.parse::<Rule>().map_err(|e| invalid(e.reason))?;
+
+            if let Neighboorhood::Range { .. } = parsed.neighboorhood {
+                return Err(invalid(
+                    "Larger than Life rules don't support bounded grids",
+                ));
+            }
+
+            if parsed.has_b0() && grid.topology == Topology::Plane {
+                return Err(invalid("B0 rules don't support bounded planes"));
+            }
+
+            parsed.grid = Some(grid);
+            return Ok(parsed);
+        }
+
+        let parts = rule.split('/').collect::<Vec<_>>();
+
+        let mut chars = rule.chars();
+
+        if chars.next().is_some_and(|c| c.eq_ignore_ascii_case(&'R'))
+            && chars.next().is_some_and(|c| c.is_ascii_digit())
+        {
+            return parse_range_rule(rule);
+        }
+
+        if parts.len() < 2 || parts.len() > 3 {
+            return Err(invalid("expected two or three parts separated by '/'"));
+        }
+
+        let mut birth = None;
+        let mut survival = None;
+        let mut states = 2;
+
+        let neighboorhood = match rule.chars().last().map(|c| c.to_ascii_uppercase()) {
+            Some('V') => Neighboorhood::VonNeumann,
+            Some('H') => Neighboorhood::Hexagonal,
+            _ => Neighboorhood::Moore,
+        };
+
+        let parts = match neighboorhood {
+            Neighboorhood::Moore => parts,
+            _ => {
+                let last = parts.len() - 1;
+                let mut parts = parts;
+                parts[last] = &parts[last][..parts[last].len() - 1];
+                parts
+            }
+        };
+
+        let starts_with_digit =
+            |part: &&str| part.chars().next().is_none_or(|c| c.is_ascii_digit());
+
+        if parts.iter().all(starts_with_digit) {
+            // S/B or S/B/C notation, only for totalistic rules
+            if !parts
+                .iter()
+                .all(|part| part.chars().all(|c| c.is_ascii_digit()))
+            {
+                return Err(invalid("S/B notation only takes neighboor counts"));
+            }
+
+            survival = Some(parse_counts(rule, parts[0], &neighboorhood)?);
+            birth = Some(parse_counts(rule, parts[1], &neighboorhood)?);
+
+            if let Some(part) = parts.get(2) {
+                states = parse_states(rule, part)?;
+            }
+        } else {
+            for part in &parts {
+                let mut chars = part.chars();
+                let kind = chars.next().map(|c| c.to_ascii_uppercase());
+                let rest = chars.as_str();
+
+                match kind {
+                    Some('B') if birth.is_none() => {
+                        birth = Some(parse_counts(rule, rest, &neighboorhood)?)
+                    }
+                    Some('S') if survival.is_none() => {
+                        survival = Some(parse_counts(rule, rest, &neighboorhood)?)
+                    }
+                    Some('C') | Some('G') => states = parse_states(rule, rest)?,
+                    Some(d) if d.is_ascii_digit() => states = parse_states(rule, part)?,
+                    _ => return Err(invalid("unknown rule part")),
+                }
+            }
+        }
+
+        match (birth, survival) {
+            (Some(birth), _) if birth[0] && states > 2 => {
+                Err(invalid("B0 is not supported by Generations rules"))
+            }
+            (Some(birth), Some(survival)) => Ok(Rule {
+                neighboorhood,
+                birth,
+                survival,
+                states,
+                grid: None,
+            }),
+            _ => Err(invalid("both birth and survival must be given")),
+        }
+    }
+}
+
+/// Neighboor counts of a transition table in Hensel notation, using the
+/// shortest of the included or excluded letters of each count
+fn format_counts(table: &[bool], neighboorhood: &Neighboorhood) -> String {
+    let mut formatted = String::new();
+
+    if *neighboorhood != Neighboorhood::Moore {
+        return (0..table.len())
+            .filter(|count| table[*count])
+            .map(|count| count.to_string())
+            .collect();
+    }
+
+    for count in 0..=8 {
+        let all = letters(count);
+
+        if all.is_empty() {
+            if (0..=255u8).any(|mask| mask.count_ones() == count && table[mask as usize]) {
+                formatted.push_str(&count.to_string());
+            }
+
+            continue;
+        }
+
+        let (included, excluded): (String, String) = all.chars().partition(|letter| {
+            let masks = configurations(count, *letter).unwrap();
+            table[masks[0] as usize]
+        });
+
+        if included.is_empty() {
+            continue;
+        }
+
+        formatted.push_str(&count.to_string());
+
+        if excluded.is_empty() {
+            continue;
+        }
+
+        if excluded.len() < included.len() {
+            formatted.push('-');
+            formatted.push_str(&excluded);
+        } else {
+            formatted.push_str(&included);
+        }
+    }
+
+    formatted
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Neighboorhood::Range {
+            range,
+            von_neumann,
+            middle,
+            ..
+        } = self.neighboorhood
+        {
+            return write!(
+                f,
+                "R{},C{},M{},S{},B{},N{}",
+                range,
+                if self.states > 2 { self.states } else { 0 },
+                middle as u8,
+                format_count_ranges(&self.survival),
+                format_count_ranges(&self.birth),
+                if von_neumann { 'N' } else { 'M' }
+            );
+        }
+
+        write!(
+            f,
+            "B{}/S{}",
+            format_counts(&self.birth, &self.neighboorhood),
+            format_counts(&self.survival, &self.neighboorhood)
+        )?;
+
+        if self.states > 2 {
+            write!(f, "/C{}", self.states)?;
+        }
+
+        write!(f, "{}", self.neighboorhood.suffix())?;
+
+        if let Some(grid) = &self.grid {
+            write!(f, ":{}", grid)?;
+        }
+
+        if let Neighboorhood::Custom(offsets) = &self.neighboorhood {
+            write!(f, " with neighboors {:?}", offsets)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// As the rule string, which custom neighboorhoods don't have
+#[cfg(feature = "serde")]
+impl serde::Serialize for Rule {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Neighboorhood::Custom(_) = self.neighboorhood {
+            let reason = "rules with custom neighboorhoods can't be serialized";
+            return Err(serde::ser::Error::custom(reason));
+        }
+
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Rule {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rule = String::deserialize(deserializer)?;
+        rule.parse().map_err(serde::de::Error::custom)
+    }
+}
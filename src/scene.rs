@@ -0,0 +1,273 @@
+use crate::common::Int;
+use crate::world::Coord;
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_scene() {
+        let content = r#"
+            # two guns facing each other
+            rule = "B3/S23"
+
+            [[pattern]]
+            file = "gun.rle"
+
+            [[pattern]]
+            file = "gun.rle"
+            position = [100, -3]
+            rotation = 180
+            flip = "vertical"
+            phase = 15
+        "#;
+
+        let scene = parse(content).unwrap();
+
+        assert_eq!(scene.rule, Some("B3/S23".to_string()));
+        assert_eq!(
+            scene.patterns,
+            vec![
+                ScenePattern {
+                    file: "gun.rle".to_string(),
+                    position: Coord(0, 0),
+                    transform: Transform::default(),
+                    phase: 0,
+                },
+                ScenePattern {
+                    file: "gun.rle".to_string(),
+                    position: Coord(100, -3),
+                    transform: Transform {
+                        rotation: 180,
+                        flip: Some(Flip::Vertical),
+                    },
+                    phase: 15,
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_invalid_scenes() {
+        let error = parse("[[pattern]]\nposition = [1, 2]\n").unwrap_err();
+        assert_eq!(error.to_string(), "pattern 1: missing file");
+
+        let error =
+            parse("[[pattern]]\nfile = \"a.rle\"\n[[pattern]]\nfile = \"b.rle\"\nrotation = 45\n")
+                .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "pattern 2: rotation must be 0, 90, 180 or 270"
+        );
+
+        let error = parse("[[pattern]]\nfile = \"a.rle\"\nposition = [1]\n").unwrap_err();
+        assert_eq!(error.to_string(), "pattern 1: position must be [x, y]");
+
+        assert_eq!(parse("").unwrap_err().to_string(), "no patterns");
+    }
+
+    #[test]
+    fn transform_cells() {
+        let l = [Coord(0, 0), Coord(0, 1), Coord(0, 2), Coord(1, 2)];
+
+        let rotated = Transform {
+            rotation: 90,
+            flip: None,
+        };
+        assert_eq!(
+            rotated.apply(&l),
+            vec![Coord(2, 0), Coord(1, 0), Coord(0, 0), Coord(0, 1)]
+        );
+
+        let flipped = Transform {
+            rotation: 0,
+            flip: Some(Flip::Horizontal),
+        };
+        assert_eq!(
+            flipped.apply(&l),
+            vec![Coord(1, 0), Coord(1, 1), Coord(1, 2), Coord(0, 2)]
+        );
+
+        let both = Transform {
+            rotation: 270,
+            flip: Some(Flip::Vertical),
+        };
+        assert_eq!(
+            both.apply(&l),
+            vec![Coord(2, 1), Coord(1, 1), Coord(0, 1), Coord(0, 0)]
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flip {
+    /// Mirrors left and right
+    Horizontal,
+    /// Mirrors top and bottom
+    Vertical,
+}
+
+/// Flips and then rotates clockwise the cells of a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Transform {
+    /// In degrees: 0, 90, 180 or 270
+    pub rotation: u32,
+    pub flip: Option<Flip>,
+}
+
+impl Transform {
+    fn apply_to(&self, c: Coord) -> Coord {
+        let c = match self.flip {
+            None => c,
+            Some(Flip::Horizontal) => Coord(-c.0, c.1),
+            Some(Flip::Vertical) => Coord(c.0, -c.1),
+        };
+
+        match self.rotation {
+            90 => Coord(-c.1, c.0),
+            180 => Coord(-c.0, -c.1),
+            270 => Coord(c.1, -c.0),
+            _ => c,
+        }
+    }
+
+    /// Transformed cells, in the same order, with the top-left corner of
+    /// their bounding box at the origin
+    pub fn apply(&self, cells: &[Coord]) -> Vec<Coord> {
+        let mut cells = cells.iter().map(|c| self.apply_to(*c)).collect::<Vec<_>>();
+
+        let min_x = cells.iter().map(|c| c.0).min().unwrap_or(0);
+        let min_y = cells.iter().map(|c| c.1).min().unwrap_or(0);
+
+        for c in &mut cells {
+            *c = Coord(c.0 - min_x, c.1 - min_y);
+        }
+
+        cells
+    }
+}
+
+/// A pattern file placed in a scene
+#[derive(Debug, PartialEq)]
+pub struct ScenePattern {
+    /// Relative to the directory of the scene file
+    pub file: String,
+    /// Where the top-left corner of the transformed pattern goes
+    pub position: Coord,
+    pub transform: Transform,
+    /// Generations the pattern is evolved once transformed and placed
+    pub phase: usize,
+}
+
+/// Several patterns placed together, to build experiments like guns facing
+/// each other
+#[derive(Debug, PartialEq)]
+pub struct Scene {
+    /// Instead of the rules of the pattern files
+    pub rule: Option<String>,
+    pub patterns: Vec<ScenePattern>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseSceneError {
+    reason: String,
+}
+
+impl fmt::Display for ParseSceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl std::error::Error for ParseSceneError {}
+
+fn read_pattern(table: &toml::Table) -> Result<ScenePattern, String> {
+    let file = match table.get("file") {
+        Some(toml::Value::String(file)) => file.clone(),
+        Some(_) => return Err("file must be a string".to_string()),
+        None => return Err("missing file".to_string()),
+    };
+
+    let position = match table.get("position") {
+        None => Coord(0, 0),
+        Some(toml::Value::Array(position)) => {
+            let coords = position
+                .iter()
+                .map(|v| v.as_integer().and_then(|v| Int::try_from(v).ok()))
+                .collect::<Option<Vec<_>>>();
+
+            match coords.as_deref() {
+                Some(&[x, y]) => Coord(x, y),
+                _ => return Err("position must be [x, y]".to_string()),
+            }
+        }
+        Some(_) => return Err("position must be [x, y]".to_string()),
+    };
+
+    let rotation = match table.get("rotation").map(toml::Value::as_integer) {
+        None => 0,
+        Some(Some(rotation @ (0 | 90 | 180 | 270))) => rotation as u32,
+        Some(_) => return Err("rotation must be 0, 90, 180 or 270".to_string()),
+    };
+
+    let flip = match table.get("flip").map(toml::Value::as_str) {
+        None => None,
+        Some(Some("horizontal")) => Some(Flip::Horizontal),
+        Some(Some("vertical")) => Some(Flip::Vertical),
+        Some(_) => return Err("flip must be \"horizontal\" or \"vertical\"".to_string()),
+    };
+
+    let phase = match table.get("phase").map(toml::Value::as_integer) {
+        None => 0,
+        Some(Some(phase)) if phase >= 0 => phase as usize,
+        Some(_) => return Err("phase must be a number of generations".to_string()),
+    };
+
+    Ok(ScenePattern {
+        file,
+        position,
+        transform: Transform { rotation, flip },
+        phase,
+    })
+}
+
+/// Parses a TOML scene: an optional `rule`, and a `[[pattern]]` table for
+/// each pattern with its `file`, and optionally its `position`,
+/// `rotation`, `flip` and `phase`
+pub fn parse(content: &str) -> Result<Scene, ParseSceneError> {
+    let error = |reason: String| ParseSceneError { reason };
+
+    let table = content
+        .parse::<toml::Table>()
+        .map_err(|e| error(e.message().to_string()))?;
+
+    let rule = match table.get("rule") {
+        None => None,
+        Some(toml::Value::String(rule)) => Some(rule.clone()),
+        Some(_) => return Err(error("rule must be a string".to_string())),
+    };
+
+    let patterns = match table.get("pattern") {
+        None => vec![],
+        Some(toml::Value::Array(patterns)) => patterns
+            .iter()
+            .enumerate()
+            .map(|(i, pattern)| {
+                match pattern {
+                    toml::Value::Table(pattern) => read_pattern(pattern),
+                    _ => Err("must be a table".to_string()),
+                }
+                .map_err(|reason| error(format!("pattern {}: {}", i + 1, reason)))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        Some(_) => return Err(error("pattern must be an array of tables".to_string())),
+    };
+
+    if patterns.is_empty() {
+        return Err(error("no patterns".to_string()));
+    }
+
+    Ok(Scene { rule, patterns })
+}
@@ -0,0 +1,291 @@
+use crate::census::Census;
+use crate::common;
+use crate::parallel::Parallelism;
+use crate::rle::LreWriter;
+use crate::world::{Coord, Coords, World};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn soups_are_reproducible() {
+        let search = SoupSearch::new(42);
+        assert_eq!(search.soup(7), search.soup(7));
+        assert_ne!(search.soup(7), search.soup(8));
+        assert!(search
+            .soup(7)
+            .iter()
+            .all(|c| c.0 >= 0 && c.0 < 16 && c.1 >= 0 && c.1 < 16));
+    }
+
+    #[test]
+    fn constant_population_is_stable() {
+        let history = vec![7, 3, 4, 4, 4, 4, 4, 4];
+        assert_eq!(stabilization(&history, 4), Some(2));
+    }
+
+    #[test]
+    fn alternating_population_is_stable() {
+        let history = vec![9, 8, 6, 3, 6, 3, 6, 3, 6, 3, 6, 3];
+        assert_eq!(stabilization(&history, 4), Some(2));
+    }
+
+    #[test]
+    fn growing_population_is_not_stable() {
+        let history = (0..100).collect::<Vec<_>>();
+        assert_eq!(stabilization(&history, 4), None);
+    }
+
+    #[test]
+    fn search_some_soups() {
+        let mut search = SoupSearch::new(1);
+        search.soups = 8;
+        search.max_generations = 1000;
+
+        let report = search.run();
+
+        assert_eq!(report.soups, 8);
+        assert!(!report.census.counts().is_empty());
+    }
+}
+
+/// Largest population period that counts as stabilization
+const MAX_PERIOD: usize = 30;
+
+/// How many times the population must repeat before calling it stable
+const STABLE_REPEATS: usize = 4;
+
+/// Minimal number of repeated generations before calling a soup stable
+const MIN_STABLE_GENERATIONS: usize = 60;
+
+/// Generations between stabilization checks
+const CHECK_INTERVAL: usize = 30;
+
+/// If the tail of a population history is periodic for long enough, returns
+/// the generation where that periodicity started
+fn stabilization(history: &[usize], min_generations: usize) -> Option<usize> {
+    (1..=MAX_PERIOD).find_map(|period| {
+        let window = (period * STABLE_REPEATS).max(min_generations);
+
+        if history.len() < window + period {
+            return None;
+        }
+
+        let periodic_since = |start: usize| {
+            history[start..]
+                .iter()
+                .zip(&history[start + period..])
+                .all(|(a, b)| a == b)
+        };
+
+        if !periodic_since(history.len() - window - period) {
+            return None;
+        }
+
+        let mut start = history.len() - window - period;
+
+        while start > 0 && history[start - 1] == history[start - 1 + period] {
+            start -= 1;
+        }
+
+        Some(start)
+    })
+}
+
+/// SplitMix64, enough for making soups reproducible from a seed
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Evolves random soups headless until they stabilize, taking a census of
+/// the resulting objects
+#[derive(Debug, Clone)]
+pub struct SoupSearch {
+    pub seed: u64,
+    pub soups: usize,
+    pub soup_size: common::Int,
+    pub max_generations: usize,
+    pub methuselah_lifespan: usize,
+    pub parallelism: Parallelism,
+}
+
+#[derive(Debug, Clone)]
+pub struct SoupResult {
+    pub index: usize,
+    pub soup: Coords,
+    pub lifespan: Option<usize>,
+    pub census: Census,
+}
+
+pub struct SearchReport {
+    pub seed: u64,
+    pub soups: usize,
+    pub census: Census,
+    pub interesting: Vec<SoupResult>,
+}
+
+impl SoupSearch {
+    pub fn new(seed: u64) -> Self {
+        SoupSearch {
+            seed,
+            soups: 1000,
+            soup_size: 16,
+            max_generations: 20000,
+            methuselah_lifespan: 5000,
+            parallelism: Parallelism::new(),
+        }
+    }
+
+    /// The cells of the soup with the given index, at 50% density
+    pub fn soup(&self, index: usize) -> Coords {
+        let mut random =
+            Random::new(self.seed ^ (index as u64).wrapping_mul(0xd1b5_4a32_d192_ed03));
+        let mut cells = Coords::new();
+
+        for y in 0..self.soup_size {
+            let mut bits = 0;
+
+            for x in 0..self.soup_size {
+                if x % 64 == 0 {
+                    bits = random.next();
+                }
+
+                if bits & (1 << (x % 64)) != 0 {
+                    cells.push(Coord(x, y));
+                }
+            }
+        }
+
+        cells
+    }
+
+    pub fn run_soup(&self, index: usize) -> SoupResult {
+        let soup = self.soup(index);
+
+        // soups already run in parallel with each other
+        let mut world = World::new();
+        world.set_parallelism(Parallelism::serial());
+        world.action(|world| {
+            for c in &soup {
+                world.make_alive(*c);
+            }
+        });
+
+        let mut history = vec![world.population_size()];
+        let mut lifespan = None;
+
+        while world.gen() < self.max_generations {
+            if world.evolve().is_err() {
+                break;
+            }
+            history.push(world.population_size());
+
+            if world.gen().is_multiple_of(CHECK_INTERVAL) {
+                lifespan = stabilization(&history, MIN_STABLE_GENERATIONS);
+
+                if lifespan.is_some() {
+                    break;
+                }
+            }
+        }
+
+        SoupResult {
+            index,
+            census: Census::of(world.cells()),
+            soup,
+            lifespan,
+        }
+    }
+
+    /// Runs all soups in parallel, keeping only the interesting ones
+    pub fn run(&self) -> SearchReport {
+        let soups = (0..self.soups).collect::<Vec<_>>();
+        let results = self
+            .parallelism
+            .for_items_of(usize::MAX)
+            .map(&soups, |index| self.run_soup(*index));
+
+        let mut census = Census::new();
+
+        for result in &results {
+            census.merge(&result.census);
+        }
+
+        SearchReport {
+            seed: self.seed,
+            soups: self.soups,
+            census,
+            interesting: results
+                .into_iter()
+                .filter(|result| result.is_interesting(self.methuselah_lifespan))
+                .collect(),
+        }
+    }
+}
+
+impl SoupResult {
+    /// Soups that never stabilize, live for long or leave uncommon objects behind
+    pub fn is_interesting(&self, methuselah_lifespan: usize) -> bool {
+        match self.lifespan {
+            None => true,
+            Some(lifespan) => {
+                lifespan >= methuselah_lifespan || self.census.uncommon().next().is_some()
+            }
+        }
+    }
+}
+
+impl SearchReport {
+    /// Writes the census as `census.txt` and each interesting soup as RLE
+    pub fn write(&self, dir: &Path) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+
+        let mut counts = self.census.counts().iter().collect::<Vec<_>>();
+        counts.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+        let mut census = fs::File::create(dir.join("census.txt"))?;
+
+        for (code, count) in counts {
+            use std::io::Write;
+            writeln!(census, "{} {}", code, count)?;
+        }
+
+        for result in &self.interesting {
+            let mut writer = LreWriter::new();
+
+            writer.comment(&format!("Soup {} of seed {}", result.index, self.seed));
+
+            match result.lifespan {
+                Some(lifespan) => writer.comment(&format!("Stabilizes at generation {}", lifespan)),
+                None => writer.comment("Does not stabilize"),
+            };
+
+            for code in result.census.uncommon() {
+                writer.comment(&format!("Leaves {}", code));
+            }
+
+            let name = format!("soup_{}_{}.rle", self.seed, result.index);
+            writer.write(&result.soup, &mut fs::File::create(dir.join(name))?)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,421 @@
+use crate::common::Int;
+use crate::rule::{CellState, Neighboorhood, ParseRuleError, Rule};
+use crate::topology::{BoundedGrid, Topology};
+use crate::world::Coord;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> WorldSnapshot {
+        WorldSnapshot {
+            generation: 1500,
+            rule: "B2/S/C3:T100,100".parse().unwrap(),
+            inverted: false,
+            cells: vec![Coord(-40, 7), Coord(-40, 8), Coord(3, -2), Coord(3, 20)],
+            dying: vec![(Coord(-41, 7), 2), (Coord(5, 5), 2)],
+        }
+    }
+
+    #[test]
+    fn write_and_read_back() {
+        let snapshot = snapshot();
+
+        let mut out = Vec::new();
+        snapshot.write(&mut out).unwrap();
+
+        assert_eq!(&out[..5], b"GOLS\x02");
+        assert_eq!(WorldSnapshot::read(&mut &out[..]).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn write_and_read_back_custom_neighboorhoods() {
+        let knight = Neighboorhood::Custom(vec![(1, 2), (2, 1), (-1, 2), (-2, 1), (12, -3)]);
+
+        let mut snapshot = snapshot();
+        snapshot.rule = Rule::totalistic(knight, &[1, 5], &[0, 2], 4);
+        snapshot
+            .rule
+            .set_bounded_grid(Some("K20*,10".parse().unwrap()));
+
+        let mut out = Vec::new();
+        snapshot.write(&mut out).unwrap();
+
+        assert_eq!(WorldSnapshot::read(&mut &out[..]).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn read_version_1() {
+        let mut out = b"GOLS\x01".to_vec();
+        write_number(&mut out, 7).unwrap();
+        out.push(0);
+        write_number(&mut out, 5).unwrap();
+        out.extend(b"B3/S2");
+        write_coords(&mut out, vec![Coord(-1, 2), Coord(0, 2)].into_iter()).unwrap();
+        write_coords(&mut out, std::iter::empty()).unwrap();
+
+        let snapshot = WorldSnapshot::read(&mut &out[..]).unwrap();
+
+        assert_eq!(snapshot.generation, 7);
+        assert_eq!(snapshot.rule, "B3/S2".parse().unwrap());
+        assert_eq!(snapshot.cells, vec![Coord(-1, 2), Coord(0, 2)]);
+    }
+
+    #[test]
+    fn cells_are_delta_encoded() {
+        let mut snapshot = snapshot();
+        snapshot.cells = (0..1000)
+            .map(|i| Coord(1_000_000 + i / 10, i % 10))
+            .collect();
+
+        let mut out = Vec::new();
+        snapshot.write(&mut out).unwrap();
+
+        // the first cell takes 4 bytes and each of the others 2 at most
+        assert!(out.len() < 2100);
+        assert_eq!(WorldSnapshot::read(&mut &out[..]).unwrap(), snapshot);
+    }
+
+    #[test]
+    fn read_invalid_snapshots() {
+        let error = WorldSnapshot::read(&mut &b"x = 3, y = 3"[..]).unwrap_err();
+        assert_eq!(error.to_string(), "not a world snapshot");
+
+        let error = WorldSnapshot::read(&mut &b"GOLS\x03"[..]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "snapshot of version 3, only versions up to 2 are supported"
+        );
+
+        let mut out = Vec::new();
+        snapshot().write(&mut out).unwrap();
+        let error = WorldSnapshot::read(&mut &out[..out.len() - 1]).unwrap_err();
+        assert!(matches!(error, ReadSnapshotError::Io(_)));
+    }
+}
+
+const MAGIC: &[u8] = b"GOLS";
+
+/// Increased on every change of the format. Snapshots of earlier versions
+/// can still be read
+pub const VERSION: u8 = 2;
+
+/// The state of a `World` at a generation, from `World::snapshot`, to be
+/// resumed later with `World::restore`
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WorldSnapshot {
+    pub generation: usize,
+    pub rule: Rule,
+    /// Whether `cells` are the dead ones, see `World::is_inverted`
+    pub inverted: bool,
+    /// Sorted
+    pub cells: Vec<Coord>,
+    /// Sorted by `Coord`
+    pub dying: Vec<(Coord, u8)>,
+}
+
+#[derive(Debug)]
+pub enum ReadSnapshotError {
+    Io(io::Error),
+    NotASnapshot,
+    UnsupportedVersion(u8),
+    Rule(ParseRuleError),
+    /// Values that no snapshot has, like coordinates out of range
+    Corrupted,
+}
+
+impl fmt::Display for ReadSnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadSnapshotError::Io(error) => write!(f, "{}", error),
+            ReadSnapshotError::NotASnapshot => write!(f, "not a world snapshot"),
+            ReadSnapshotError::UnsupportedVersion(version) => write!(
+                f,
+                "snapshot of version {}, only versions up to {} are supported",
+                version, VERSION
+            ),
+            ReadSnapshotError::Rule(error) => write!(f, "{}", error),
+            ReadSnapshotError::Corrupted => write!(f, "corrupted snapshot"),
+        }
+    }
+}
+
+impl std::error::Error for ReadSnapshotError {}
+
+impl From<io::Error> for ReadSnapshotError {
+    fn from(error: io::Error) -> Self {
+        ReadSnapshotError::Io(error)
+    }
+}
+
+/// LEB128, 7 bits per byte, lowest first
+fn write_number(out: &mut dyn Write, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+
+        if n == 0 {
+            return out.write_all(&[byte]);
+        }
+
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_number(input: &mut dyn Read) -> Result<u64, ReadSnapshotError> {
+    let mut n = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        input.read_exact(&mut byte)?;
+        n |= ((byte[0] & 0x7f) as u64) << shift;
+
+        if byte[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+
+    Err(ReadSnapshotError::Corrupted)
+}
+
+/// Zigzag encoded, so that small negative numbers stay short
+fn write_signed(out: &mut dyn Write, n: i64) -> io::Result<()> {
+    write_number(out, ((n << 1) ^ (n >> 63)) as u64)
+}
+
+fn read_signed(input: &mut dyn Read) -> Result<i64, ReadSnapshotError> {
+    let n = read_number(input)?;
+    Ok((n >> 1) as i64 ^ -((n & 1) as i64))
+}
+
+/// Sorted coordinates as differences to the previous ones, which are small
+/// for the cells of a pattern
+fn write_coords(
+    out: &mut dyn Write,
+    coords: impl ExactSizeIterator<Item = Coord>,
+) -> io::Result<()> {
+    write_number(out, coords.len() as u64)?;
+
+    let mut previous = Coord(0, 0);
+
+    for c in coords {
+        write_signed(out, c.0 as i64 - previous.0 as i64)?;
+        write_signed(out, c.1 as i64 - previous.1 as i64)?;
+        previous = c;
+    }
+
+    Ok(())
+}
+
+fn read_coords(input: &mut dyn Read) -> Result<Vec<Coord>, ReadSnapshotError> {
+    let len = read_number(input)?;
+
+    let mut coords = vec![];
+    let mut previous = Coord(0, 0);
+
+    for _ in 0..len {
+        let x = previous.0 as i64 + read_signed(input)?;
+        let y = previous.1 as i64 + read_signed(input)?;
+
+        previous = match (Int::try_from(x), Int::try_from(y)) {
+            (Ok(x), Ok(y)) => Coord(x, y),
+            _ => return Err(ReadSnapshotError::Corrupted),
+        };
+
+        coords.push(previous);
+    }
+
+    Ok(coords)
+}
+
+fn write_string(out: &mut dyn Write, string: &str) -> io::Result<()> {
+    write_number(out, string.len() as u64)?;
+    out.write_all(string.as_bytes())
+}
+
+fn read_string(input: &mut dyn Read) -> Result<String, ReadSnapshotError> {
+    let mut string = vec![];
+    let len = read_number(input)?;
+    input.take(len).read_to_end(&mut string)?;
+
+    if string.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+
+    String::from_utf8(string).map_err(|_| ReadSnapshotError::Corrupted)
+}
+
+fn write_counts(out: &mut dyn Write, counts: &[usize]) -> io::Result<()> {
+    write_number(out, counts.len() as u64)?;
+    counts.iter().try_for_each(|n| write_number(out, *n as u64))
+}
+
+fn read_counts(input: &mut dyn Read) -> Result<Vec<usize>, ReadSnapshotError> {
+    let len = read_number(input)?;
+    (0..len).map(|_| Ok(read_number(input)? as usize)).collect()
+}
+
+/// Kinds of rules, as written before them
+const RULE_STRING: u8 = 0;
+const CUSTOM_RULE: u8 = 1;
+
+/// Rules with custom neighboorhoods have no rule string, so they are written
+/// as their offsets, the counts of live neighboors for births and survivals,
+/// the number of states and the bounded grid (empty if none). Other rules
+/// are written as their rule string
+fn write_rule(out: &mut dyn Write, rule: &Rule) -> io::Result<()> {
+    let offsets = match rule.neighboorhood() {
+        Neighboorhood::Custom(offsets) => offsets,
+        _ => {
+            out.write_all(&[RULE_STRING])?;
+            return write_string(out, &rule.to_string());
+        }
+    };
+
+    out.write_all(&[CUSTOM_RULE])?;
+
+    write_number(out, offsets.len() as u64)?;
+
+    for (x, y) in offsets {
+        write_signed(out, *x as i64)?;
+        write_signed(out, *y as i64)?;
+    }
+
+    let counts = |state| {
+        (0..=offsets.len())
+            .filter(|n| rule.mutate_count(state, *n) == CellState::Alive)
+            .collect::<Vec<_>>()
+    };
+
+    write_counts(out, &counts(CellState::Dead))?;
+    write_counts(out, &counts(CellState::Alive))?;
+    out.write_all(&[rule.states()])?;
+
+    let grid = rule.bounded_grid().map(|grid| grid.to_string());
+    write_string(out, grid.as_deref().unwrap_or(""))
+}
+
+fn read_rule(input: &mut dyn Read) -> Result<Rule, ReadSnapshotError> {
+    let mut kind = [0];
+    input.read_exact(&mut kind)?;
+
+    match kind[0] {
+        RULE_STRING => {}
+        CUSTOM_RULE => return read_custom_rule(input),
+        _ => return Err(ReadSnapshotError::Corrupted),
+    }
+
+    read_string(input)?
+        .parse::<Rule>()
+        .map_err(ReadSnapshotError::Rule)
+}
+
+fn read_custom_rule(input: &mut dyn Read) -> Result<Rule, ReadSnapshotError> {
+    let len = read_number(input)?;
+
+    // the limit of `Rule::totalistic`
+    if len > 64 {
+        return Err(ReadSnapshotError::Corrupted);
+    }
+
+    let mut offsets = vec![];
+
+    for _ in 0..len {
+        let x = Int::try_from(read_signed(input)?).map_err(|_| ReadSnapshotError::Corrupted)?;
+        let y = Int::try_from(read_signed(input)?).map_err(|_| ReadSnapshotError::Corrupted)?;
+        offsets.push((x, y));
+    }
+
+    let birth = read_counts(input)?;
+    let survival = read_counts(input)?;
+
+    let mut states = [0];
+    input.read_exact(&mut states)?;
+
+    let grid = match read_string(input)?.as_str() {
+        "" => None,
+        grid => Some(
+            grid.parse::<BoundedGrid>()
+                .map_err(|_| ReadSnapshotError::Corrupted)?,
+        ),
+    };
+
+    let b0_on_plane = birth.contains(&0) && grid.is_some_and(|g| g.topology == Topology::Plane);
+
+    if states[0] < 2 || b0_on_plane {
+        return Err(ReadSnapshotError::Corrupted);
+    }
+
+    let mut rule = Rule::totalistic(Neighboorhood::Custom(offsets), &birth, &survival, states[0]);
+    rule.set_bounded_grid(grid);
+
+    Ok(rule)
+}
+
+impl WorldSnapshot {
+    /// Writes the magic bytes `GOLS` and the format version, followed by the
+    /// generation, whether the cells are inverted, the rule (see
+    /// `write_rule`), and the delta encoded cells and dying cells with their
+    /// states
+    pub fn write(&self, out: &mut dyn Write) -> io::Result<()> {
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION])?;
+
+        write_number(out, self.generation as u64)?;
+        out.write_all(&[self.inverted as u8])?;
+
+        write_rule(out, &self.rule)?;
+
+        write_coords(out, self.cells.iter().copied())?;
+        write_coords(out, self.dying.iter().map(|d| d.0))?;
+
+        let states = self.dying.iter().map(|d| d.1).collect::<Vec<_>>();
+        out.write_all(&states)
+    }
+
+    pub fn read(input: &mut dyn Read) -> Result<WorldSnapshot, ReadSnapshotError> {
+        let mut magic = [0; 4];
+
+        if input.read_exact(&mut magic).is_err() || magic != MAGIC {
+            return Err(ReadSnapshotError::NotASnapshot);
+        }
+
+        let mut version = [0];
+        input.read_exact(&mut version)?;
+
+        if version[0] == 0 || version[0] > VERSION {
+            return Err(ReadSnapshotError::UnsupportedVersion(version[0]));
+        }
+
+        let generation = read_number(input)?;
+
+        let mut inverted = [0];
+        input.read_exact(&mut inverted)?;
+
+        // version 1 only had rule strings
+        let rule = match version[0] {
+            1 => read_string(input)?
+                .parse::<Rule>()
+                .map_err(ReadSnapshotError::Rule)?,
+            _ => read_rule(input)?,
+        };
+
+        let cells = read_coords(input)?;
+        let dying = read_coords(input)?;
+
+        let mut states = vec![0; dying.len()];
+        input.read_exact(&mut states)?;
+
+        Ok(WorldSnapshot {
+            generation: generation as usize,
+            rule,
+            inverted: inverted[0] != 0,
+            cells,
+            dying: dying.into_iter().zip(states).collect(),
+        })
+    }
+}
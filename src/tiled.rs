@@ -0,0 +1,478 @@
+use crate::common;
+use crate::parallel::Parallelism;
+use crate::rule::{CellState, Neighboorhood, Rule};
+use crate::world::{Coord, Coords};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(mut cells: Coords) -> Coords {
+        cells.sort();
+        cells
+    }
+
+    #[test]
+    fn transpose_tile() {
+        let mut rows = [0u64; TILE_SIZE];
+        rows[0] = 0b110;
+        rows[5] = 1 << 63;
+
+        let columns = transpose(&rows);
+
+        assert_eq!(columns[1], 1);
+        assert_eq!(columns[2], 1);
+        assert_eq!(columns[63], 1 << 5);
+        assert_eq!(transpose(&columns), rows);
+    }
+
+    #[test]
+    fn cells_are_sorted_across_tiles() {
+        let cells = sorted(vec![
+            Coord(-1, -1),
+            Coord(-1, 64),
+            Coord(0, 0),
+            Coord(63, -65),
+            Coord(64, 3),
+            Coord(200, -1000),
+        ]);
+
+        let universe = TiledUniverse::from_cells(&cells);
+
+        assert_eq!(universe.population(), 6);
+        assert_eq!(universe.cells(), cells);
+    }
+
+    #[test]
+    fn bounding_box_and_window_across_tiles() {
+        let cells = sorted(vec![
+            Coord(-70, 5),
+            Coord(-1, -1),
+            Coord(0, 130),
+            Coord(63, -65),
+            Coord(64, 3),
+        ]);
+
+        let universe = TiledUniverse::from_cells(&cells);
+
+        assert_eq!(
+            universe.bounding_box(),
+            Some((Coord(-70, -65), Coord(64, 130)))
+        );
+        assert_eq!(TiledUniverse::new().bounding_box(), None);
+
+        assert_eq!(
+            universe.cells_in(Coord(-1, -65), Coord(63, 129)),
+            vec![Coord(-1, -1), Coord(63, -65)]
+        );
+        assert_eq!(universe.cells_in(Coord(-100, -100), Coord(100, 200)), cells);
+    }
+
+    #[test]
+    fn blinker_across_tile_corners() {
+        let rule = Rule::default();
+        let mut universe = TiledUniverse::from_cells(&[Coord(-1, 0), Coord(0, 0), Coord(1, 0)]);
+
+        universe.step(&rule);
+        assert_eq!(
+            universe.cells(),
+            vec![Coord(0, -1), Coord(0, 0), Coord(0, 1)]
+        );
+
+        universe.step(&rule);
+        assert_eq!(
+            universe.cells(),
+            vec![Coord(-1, 0), Coord(0, 0), Coord(1, 0)]
+        );
+    }
+
+    #[test]
+    fn glider_crosses_tiles() {
+        let rule = Rule::default();
+        let glider = [
+            Coord(61, 60),
+            Coord(62, 61),
+            Coord(60, 62),
+            Coord(61, 62),
+            Coord(62, 62),
+        ];
+        let mut universe = TiledUniverse::from_cells(&sorted(glider.to_vec()));
+
+        for _ in 0..40 {
+            universe.step(&rule);
+        }
+
+        let moved = glider.iter().map(|c| Coord(c.0 + 10, c.1 + 10)).collect();
+        assert_eq!(universe.cells(), sorted(moved));
+    }
+
+    #[test]
+    fn still_lifes_are_left_alone() {
+        let rule = Rule::default();
+        let block = [Coord(0, 0), Coord(0, 1), Coord(1, 0), Coord(1, 1)];
+        let mut universe = TiledUniverse::from_cells(&block);
+
+        universe.step(&rule);
+        universe.step(&rule);
+
+        assert!(universe.changed.is_empty());
+        assert_eq!(universe.cells(), block.to_vec());
+    }
+
+    #[test]
+    fn supported_rules() {
+        assert!(TiledUniverse::supports(&Rule::default()));
+        assert!(TiledUniverse::supports(&"B36/S23".parse().unwrap()));
+        assert!(!TiledUniverse::supports(&"B2a/S23".parse().unwrap()));
+        assert!(!TiledUniverse::supports(&"B2/S/C3".parse().unwrap()));
+        assert!(!TiledUniverse::supports(&"B2/S3V".parse().unwrap()));
+        assert!(!TiledUniverse::supports(&"B3/S23:T10,10".parse().unwrap()));
+        assert!(!TiledUniverse::supports(&"B013/S0124".parse().unwrap()));
+    }
+}
+
+const TILE_SIZE: usize = 64;
+
+/// Bit x of row y is the cell (x, y) of the tile
+type Tile = [u64; TILE_SIZE];
+
+const EMPTY: Tile = [0; TILE_SIZE];
+
+type TileKey = (common::Int, common::Int);
+
+fn tile_of(c: Coord) -> (TileKey, usize, usize) {
+    let size = TILE_SIZE as common::Int;
+
+    (
+        (c.0.div_euclid(size), c.1.div_euclid(size)),
+        c.0.rem_euclid(size) as usize,
+        c.1.rem_euclid(size) as usize,
+    )
+}
+
+/// Swaps rows and columns, so bit y of word x is the cell (x, y)
+fn transpose(rows: &Tile) -> Tile {
+    let mut a = *rows;
+    let mut j = 32;
+    let mut m: u64 = 0x0000_0000_ffff_ffff;
+
+    while j != 0 {
+        let mut k = 0;
+
+        while k < TILE_SIZE {
+            let t = ((a[k] >> j) ^ a[k + j]) & m;
+            a[k] ^= t << j;
+            a[k + j] ^= t;
+            k = (k + j + 1) & !j;
+        }
+
+        j >>= 1;
+        m ^= m << j;
+    }
+
+    a
+}
+
+fn half_adder(a: u64, b: u64) -> (u64, u64) {
+    (a ^ b, a & b)
+}
+
+fn full_adder(a: u64, b: u64, c: u64) -> (u64, u64) {
+    let s = a ^ b;
+    (s ^ c, (a & b) | (c & s))
+}
+
+/// Live cells stored in sparse 64x64 tiles of bit rows, evolved 64 cells at
+/// a time with bit-parallel adders, QuickLife style. Tiles whose
+/// neighboorhood didn't change in the last generation are skipped.
+///
+/// Only runs totalistic two-state rules on the Moore neighboorhood, on an
+/// unbounded grid and without B0 (see `TiledUniverse::supports`)
+#[derive(Debug, Clone, Default)]
+pub struct TiledUniverse {
+    tiles: HashMap<TileKey, Tile>,
+    /// Tiles that changed in the last generation, and so whose neighboors
+    /// must be computed in the next one
+    changed: HashSet<TileKey>,
+    parallelism: Parallelism,
+}
+
+impl TiledUniverse {
+    pub fn new() -> Self {
+        TiledUniverse {
+            tiles: HashMap::new(),
+            changed: HashSet::new(),
+            parallelism: Parallelism::new(),
+        }
+    }
+
+    pub fn from_cells(cells: &[Coord]) -> Self {
+        let mut universe = TiledUniverse::new();
+
+        for c in cells {
+            universe.make_alive(*c);
+        }
+
+        universe
+    }
+
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        self.parallelism = parallelism;
+    }
+
+    pub fn supports(rule: &Rule) -> bool {
+        *rule.neighboorhood() == Neighboorhood::Moore
+            && rule.is_totalistic()
+            && rule.states() == 2
+            && rule.bounded_grid().is_none()
+            && !rule.has_b0()
+    }
+
+    pub fn make_alive(&mut self, c: Coord) {
+        let (key, x, y) = tile_of(c);
+        self.tiles.entry(key).or_insert(EMPTY)[y] |= 1 << x;
+        self.changed.insert(key);
+    }
+
+    pub fn population(&self) -> usize {
+        self.tiles
+            .values()
+            .map(|tile| {
+                tile.iter()
+                    .map(|row| row.count_ones() as usize)
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    /// Live cells sorted by `Coord`
+    pub fn cells(&self) -> Coords {
+        let mut columns = BTreeMap::<common::Int, Vec<(common::Int, &Tile)>>::new();
+
+        for (key, tile) in &self.tiles {
+            columns.entry(key.0).or_default().push((key.1, tile));
+        }
+
+        let size = TILE_SIZE as common::Int;
+
+        for tiles in columns.values_mut() {
+            tiles.sort_unstable_by_key(|(ty, _)| *ty);
+        }
+
+        let columns = columns.into_iter().collect::<Vec<_>>();
+
+        self.parallelism
+            .for_items_of(TILE_SIZE * TILE_SIZE)
+            .map(&columns, |(tx, tiles)| {
+                let tx = *tx;
+                let tiles = tiles
+                    .iter()
+                    .map(|(ty, tile)| (*ty, transpose(tile)))
+                    .collect::<Vec<_>>();
+
+                let mut cells = Coords::new();
+
+                for x in 0..TILE_SIZE {
+                    for (ty, columns) in &tiles {
+                        let mut column = columns[x];
+
+                        while column != 0 {
+                            let y = column.trailing_zeros() as common::Int;
+                            column &= column - 1;
+                            cells.push(Coord(tx * size + x as common::Int, ty * size + y));
+                        }
+                    }
+                }
+
+                cells
+            })
+            .concat()
+    }
+
+    /// Top-left and bottom-right corners (inclusive) of the live cells
+    pub fn bounding_box(&self) -> Option<(Coord, Coord)> {
+        let size = TILE_SIZE as common::Int;
+        let mut bounds: Option<(Coord, Coord)> = None;
+
+        for (key, tile) in &self.tiles {
+            let columns = tile.iter().fold(0, |columns, row| columns | row);
+            let first_row = tile.iter().position(|row| *row != 0);
+            let last_row = tile.iter().rposition(|row| *row != 0);
+
+            let (first_row, last_row) = match (first_row, last_row) {
+                (Some(first), Some(last)) => (first, last),
+                _ => continue,
+            };
+
+            let min = Coord(
+                key.0 * size + columns.trailing_zeros() as common::Int,
+                key.1 * size + first_row as common::Int,
+            );
+            let max = Coord(
+                key.0 * size + (63 - columns.leading_zeros()) as common::Int,
+                key.1 * size + last_row as common::Int,
+            );
+
+            bounds = Some(match bounds {
+                None => (min, max),
+                Some((a, b)) => (
+                    Coord(a.0.min(min.0), a.1.min(min.1)),
+                    Coord(b.0.max(max.0), b.1.max(max.1)),
+                ),
+            });
+        }
+
+        bounds
+    }
+
+    /// Live cells from `min` to `max` (inclusive), sorted by `Coord`
+    pub fn cells_in(&self, min: Coord, max: Coord) -> Coords {
+        let size = TILE_SIZE as common::Int;
+        let ((min_tx, min_ty), _, _) = tile_of(min);
+        let ((max_tx, max_ty), _, _) = tile_of(max);
+
+        let mut cells = Coords::new();
+
+        for (key, tile) in &self.tiles {
+            if key.0 < min_tx || key.0 > max_tx || key.1 < min_ty || key.1 > max_ty {
+                continue;
+            }
+
+            for (y, row) in tile.iter().enumerate() {
+                let mut row = *row;
+
+                while row != 0 {
+                    let x = row.trailing_zeros() as common::Int;
+                    row &= row - 1;
+
+                    let c = Coord(key.0 * size + x, key.1 * size + y as common::Int);
+
+                    if c.0 >= min.0 && c.0 <= max.0 && c.1 >= min.1 && c.1 <= max.1 {
+                        cells.push(c);
+                    }
+                }
+            }
+        }
+
+        cells.sort_unstable();
+        cells
+    }
+
+    /// Evolves one generation with a rule it `supports`
+    pub fn step(&mut self, rule: &Rule) {
+        debug_assert!(TiledUniverse::supports(rule));
+
+        let birth = (0..=8).map(|n| rule.mutate(CellState::Dead, (1 << n) - 1) == CellState::Alive);
+        let survival =
+            (0..=8).map(|n| rule.mutate(CellState::Alive, (1 << n) - 1) == CellState::Alive);
+        let transitions = birth.zip(survival).collect::<Vec<_>>();
+
+        let mut keys = HashSet::new();
+
+        for key in &self.changed {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    keys.insert((key.0 + dx, key.1 + dy));
+                }
+            }
+        }
+
+        let tiles = &self.tiles;
+        let keys = keys.into_iter().collect::<Vec<_>>();
+
+        let next = self
+            .parallelism
+            .for_items_of(TILE_SIZE * TILE_SIZE)
+            .map(&keys, |key| (*key, next_tile(tiles, *key, &transitions)));
+
+        self.changed.clear();
+
+        for (key, tile) in next {
+            if *self.tiles.get(&key).unwrap_or(&EMPTY) == tile {
+                continue;
+            }
+
+            self.changed.insert(key);
+
+            if tile == EMPTY {
+                self.tiles.remove(&key);
+            } else {
+                self.tiles.insert(key, tile);
+            }
+        }
+    }
+}
+
+/// Next generation of a tile, given (birth, survival) for each neighboor count
+fn next_tile(tiles: &HashMap<TileKey, Tile>, key: TileKey, transitions: &[(bool, bool)]) -> Tile {
+    // looked up once, rather than for every row
+    let mut around = [[&EMPTY; 3]; 3];
+
+    for (dy, row) in around.iter_mut().enumerate() {
+        for (dx, tile) in row.iter_mut().enumerate() {
+            let neighboor = (key.0 + dx as common::Int - 1, key.1 + dy as common::Int - 1);
+            *tile = tiles.get(&neighboor).unwrap_or(&EMPTY);
+        }
+    }
+
+    let tile = |dx: isize, dy: isize| around[(dy + 1) as usize][(dx + 1) as usize];
+
+    // rows -1 to 64 of the tile, with the cells to their west and east
+    let mut west = [0u64; TILE_SIZE + 2];
+    let mut center = [0u64; TILE_SIZE + 2];
+    let mut east = [0u64; TILE_SIZE + 2];
+
+    for i in 0..TILE_SIZE + 2 {
+        let (dy, row) = match i {
+            0 => (-1, TILE_SIZE - 1),
+            i if i == TILE_SIZE + 1 => (1, 0),
+            i => (0, i - 1),
+        };
+
+        let c = tile(0, dy)[row];
+        center[i] = c;
+        west[i] = (c << 1) | (tile(-1, dy)[row] >> 63);
+        east[i] = (c >> 1) | (tile(1, dy)[row] << 63);
+    }
+
+    let mut next = EMPTY;
+
+    for (y, out) in next.iter_mut().enumerate() {
+        let (above, row, below) = (y, y + 1, y + 2);
+
+        let (s1, c1) = full_adder(west[above], center[above], east[above]);
+        let (s2, c2) = full_adder(west[row], east[row], west[below]);
+        let (s3, c3) = half_adder(center[below], east[below]);
+
+        let (ones, c4) = full_adder(s1, s2, s3);
+        let (t1, c5) = full_adder(c1, c2, c3);
+        let (twos, c6) = half_adder(t1, c4);
+        let (fours, eights) = half_adder(c5, c6);
+
+        let alive = center[row];
+        let mut result = 0;
+
+        for (count, (birth, survival)) in transitions.iter().enumerate() {
+            if !birth && !survival {
+                continue;
+            }
+
+            let bit = |plane: u64, i: usize| if count & (1 << i) != 0 { plane } else { !plane };
+            let has_count = bit(ones, 0) & bit(twos, 1) & bit(fours, 2) & bit(eights, 3);
+
+            let cells = match (birth, survival) {
+                (true, true) => !0,
+                (true, false) => !alive,
+                (false, true) => alive,
+                (false, false) => 0,
+            };
+
+            result |= has_count & cells;
+        }
+
+        *out = result;
+    }
+
+    next
+}
@@ -0,0 +1,290 @@
+use crate::common;
+use crate::world::Coord;
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(spec: &str) -> BoundedGrid {
+        spec.parse().unwrap()
+    }
+
+    #[test]
+    fn parse_and_display() {
+        for spec in &["T100,80", "P20,10", "K30*,20", "K30,20*", "C8,8", "T0,16"] {
+            assert_eq!(grid(spec).to_string(), *spec);
+        }
+
+        assert_eq!(grid("t10").to_string(), "T10,10");
+
+        for invalid in &[
+            "", "X10,10", "T-1,4", "K10,10", "K10*,10*", "C0,4", "P10*,4",
+        ] {
+            assert!(invalid.parse::<BoundedGrid>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn grid_is_centered_on_the_origin() {
+        let g = grid("P5,4");
+
+        assert!(g.contains(Coord(-2, -2)));
+        assert!(g.contains(Coord(2, 1)));
+        assert!(!g.contains(Coord(3, 0)));
+        assert!(!g.contains(Coord(0, 2)));
+    }
+
+    #[test]
+    fn plane_clips() {
+        assert_eq!(grid("P4,4").wrap(Coord(1, 1)), Some(Coord(1, 1)));
+        assert_eq!(grid("P4,4").wrap(Coord(2, 1)), None);
+    }
+
+    #[test]
+    fn torus_wraps_around() {
+        let g = grid("T4,6");
+
+        assert_eq!(g.wrap(Coord(2, 0)), Some(Coord(-2, 0)));
+        assert_eq!(g.wrap(Coord(-3, -4)), Some(Coord(1, 2)));
+    }
+
+    #[test]
+    fn infinite_torus_dimension() {
+        let g = grid("T0,4");
+
+        assert_eq!(g.wrap(Coord(1000, 2)), Some(Coord(1000, -2)));
+    }
+
+    #[test]
+    fn klein_bottle_twists_one_pair_of_edges() {
+        // top and bottom are twisted, so crossing them reflects x
+        let g = grid("K4*,4");
+        assert_eq!(g.wrap(Coord(-2, 2)), Some(Coord(1, -2)));
+        assert_eq!(g.wrap(Coord(2, -1)), Some(Coord(-2, -1)));
+
+        let g = grid("K4,4*");
+        assert_eq!(g.wrap(Coord(2, -2)), Some(Coord(-2, 1)));
+    }
+
+    #[test]
+    fn cross_surface_twists_both() {
+        let g = grid("C4,4");
+
+        assert_eq!(g.wrap(Coord(-2, 2)), Some(Coord(1, -2)));
+        assert_eq!(g.wrap(Coord(2, -2)), Some(Coord(-2, 1)));
+    }
+}
+
+/// How the edges of a bounded grid are joined
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Topology {
+    /// Cells beyond the edges are always dead
+    Plane,
+    Torus,
+    /// Like a torus, with the top and bottom edges (`twisted_width`) or the
+    /// left and right edges joined reversed
+    KleinBottle {
+        twisted_width: bool,
+    },
+    /// Both pairs of edges joined reversed
+    CrossSurface,
+}
+
+/// Golly's bounded grids (`:T100,80`, `:P`, `:K` and `:C`), centered on the
+/// origin. A size of zero leaves that dimension unbounded (planes and tori
+/// only)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundedGrid {
+    pub topology: Topology,
+    pub width: common::Int,
+    pub height: common::Int,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseGridError {
+    grid: String,
+    pub(crate) reason: &'static str,
+}
+
+impl fmt::Display for ParseGridError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid bounded grid \"{}\": {}", self.grid, self.reason)
+    }
+}
+
+impl std::error::Error for ParseGridError {}
+
+/// Left (or top) edge and size of a dimension, if bounded
+fn edges(size: common::Int) -> Option<(common::Int, common::Int)> {
+    match size {
+        0 => None,
+        size => Some((-(size / 2), size)),
+    }
+}
+
+/// Position inside `[first, first + size)` and how many times the edges
+/// were crossed to get there
+fn wrap_dimension(
+    v: common::Int,
+    first: common::Int,
+    size: common::Int,
+) -> (common::Int, common::Int) {
+    let crossings = (v - first).div_euclid(size);
+    (first + (v - first).rem_euclid(size), crossings)
+}
+
+impl BoundedGrid {
+    pub fn contains(&self, c: Coord) -> bool {
+        let inside = |v: common::Int, size| match edges(size) {
+            None => true,
+            Some((first, size)) => v >= first && v < first + size,
+        };
+
+        inside(c.0, self.width) && inside(c.1, self.height)
+    }
+
+    /// Number of cells of the grid, None if a dimension is unbounded
+    pub fn area(&self) -> Option<usize> {
+        match (self.width, self.height) {
+            (0, _) | (_, 0) => None,
+            (width, height) => Some(width as usize * height as usize),
+        }
+    }
+
+    /// Whether crossing some edge reflects the other coordinate
+    pub fn is_twisted(&self) -> bool {
+        !matches!(self.topology, Topology::Plane | Topology::Torus)
+    }
+
+    /// The cell of the grid a coordinate maps to, if any
+    pub fn wrap(&self, c: Coord) -> Option<Coord> {
+        if self.contains(c) {
+            return Some(c);
+        }
+
+        if self.topology == Topology::Plane {
+            return None;
+        }
+
+        let (twisted_width, twisted_height) = match self.topology {
+            Topology::KleinBottle { twisted_width } => (twisted_width, !twisted_width),
+            Topology::CrossSurface => (true, true),
+            _ => (false, false),
+        };
+
+        let reflect =
+            |v: common::Int, first: common::Int, size: common::Int| first + (first + size - 1 - v);
+
+        let Coord(mut x, mut y) = c;
+
+        if let Some((first, size)) = edges(self.height) {
+            let (wrapped, crossings) = wrap_dimension(y, first, size);
+            y = wrapped;
+
+            if twisted_width && crossings % 2 != 0 {
+                let (first_x, width) = edges(self.width).unwrap();
+                x = reflect(x, first_x, width);
+            }
+        }
+
+        if let Some((first, size)) = edges(self.width) {
+            let (wrapped, crossings) = wrap_dimension(x, first, size);
+            x = wrapped;
+
+            if twisted_height && crossings % 2 != 0 {
+                let (first_y, height) = edges(self.height).unwrap();
+                y = reflect(y, first_y, height);
+            }
+        }
+
+        Some(Coord(x, y))
+    }
+}
+
+impl FromStr for BoundedGrid {
+    type Err = ParseGridError;
+
+    /// Parses the part of a rule after ':', like `T100,80` or `K30*,20`
+    fn from_str(grid: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| ParseGridError {
+            grid: grid.to_string(),
+            reason,
+        };
+
+        let mut chars = grid.chars();
+        let kind = chars.next().map(|c| c.to_ascii_uppercase());
+        let sizes = chars.as_str().split(',').collect::<Vec<_>>();
+
+        if sizes.len() > 2 {
+            return Err(invalid("expected a width and an optional height"));
+        }
+
+        let size = |size: &str| {
+            size.trim_end_matches('*')
+                .parse::<common::Int>()
+                .ok()
+                .filter(|size| *size >= 0)
+                .ok_or_else(|| invalid("sizes must be non-negative numbers"))
+        };
+
+        let width = size(sizes[0])?;
+        let height = match sizes.get(1) {
+            Some(height) => size(height)?,
+            None => width,
+        };
+
+        let twisted_width = sizes[0].ends_with('*');
+        let twisted_height = sizes.get(1).is_some_and(|height| height.ends_with('*'));
+
+        let topology = match (kind, twisted_width, twisted_height) {
+            (Some('P'), false, false) => Topology::Plane,
+            (Some('T'), false, false) => Topology::Torus,
+            (Some('K'), true, false) | (Some('K'), false, true) => {
+                Topology::KleinBottle { twisted_width }
+            }
+            (Some('K'), _, _) => return Err(invalid("one pair of edges must be twisted")),
+            (Some('C'), false, false) => Topology::CrossSurface,
+            (Some('P'), _, _) | (Some('T'), _, _) | (Some('C'), _, _) => {
+                return Err(invalid("only Klein bottles have a twisted pair of edges"))
+            }
+            _ => return Err(invalid("unknown topology")),
+        };
+
+        let grid = BoundedGrid {
+            topology,
+            width,
+            height,
+        };
+
+        if grid.is_twisted() && (width == 0 || height == 0) {
+            return Err(invalid("twisted edges need a bounded width and height"));
+        }
+
+        Ok(grid)
+    }
+}
+
+impl fmt::Display for BoundedGrid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (kind, twisted_width, twisted_height) = match self.topology {
+            Topology::Plane => ('P', false, false),
+            Topology::Torus => ('T', false, false),
+            Topology::KleinBottle { twisted_width } => ('K', twisted_width, !twisted_width),
+            Topology::CrossSurface => ('C', false, false),
+        };
+
+        let star = |twisted| if twisted { "*" } else { "" };
+
+        write!(
+            f,
+            "{}{}{},{}{}",
+            kind,
+            self.width,
+            star(twisted_width),
+            self.height,
+            star(twisted_height)
+        )
+    }
+}
@@ -1,3 +1,4 @@
+use crate::common;
 use crate::rle;
 use crate::world::{Coord, World};
 
@@ -17,4 +18,18 @@ impl<'a> rle::LifePlaceMaker for WorldLifePlaceMaker<'a> {
         self.world
             .make_alive(Coord(self.position.0 + coord.0, self.position.1 + coord.1));
     }
+
+    fn make_run_alive(&mut self, start: Coord, len: common::Int) {
+        let (x, y) = (self.position.0 + start.0, self.position.1 + start.1);
+        self.world.extend_alive((x..x + len).map(|x| Coord(x, y)));
+    }
+
+    fn make_cell_state(&mut self, coord: Coord, state: u8) {
+        let coord = Coord(self.position.0 + coord.0, self.position.1 + coord.1);
+
+        match state {
+            1 => self.world.make_alive(coord),
+            _ => self.world.make_dying(coord, state),
+        }
+    }
 }
@@ -1,10 +1,32 @@
 use crate::common;
-use rayon::prelude::*;
+use crate::ltl;
+use crate::parallel::Parallelism;
+use crate::recorder::{GenerationStats, PopulationRecorder};
+use crate::rule::{B0Emulation, CellState, Neighboorhood, Rule};
+use crate::snapshot::WorldSnapshot;
+use crate::tiled::TiledUniverse;
+use crate::topology::BoundedGrid;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::OnceLock;
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn mutate(state: CellState, neighboors: u32) -> CellState {
+        let mask = (1u64 << neighboors) - 1;
+        Rule::default().mutate(state, mask)
+    }
+
+    fn cells_that_may_be_born(ic: &InterestingCells) -> usize {
+        ic.neighboor_masks()
+            .iter()
+            .filter(|(c, _)| ic.alive.binary_search(c).is_err())
+            .count()
+    }
+
     #[test]
     fn cell_stays_dead() {
         assert_eq!(mutate(CellState::Dead, 0), CellState::Dead);
@@ -52,7 +74,9 @@ mod tests {
             Coord(-1, 0),
         ];
 
-        assert_eq!(neighboors(Coord(0, 0)).iter().collect::<Vec<_>>(), expected);
+        let moore = Neighboorhood::Moore;
+        let n = neighboors(Coord(0, 0), moore.offsets(), None);
+        assert_eq!(n.iter().collect::<Vec<_>>(), expected);
     }
 
     #[test]
@@ -62,7 +86,7 @@ mod tests {
 
         let n = ic.live_neighboors(Coord(0, 0));
 
-        assert_eq!(ic.dead.len(), 0);
+        assert_eq!(cells_that_may_be_born(&ic), 0);
         assert_eq!(n.count(), 0);
     }
 
@@ -74,7 +98,7 @@ mod tests {
 
         let n = ic.live_neighboors(Coord(0, 0));
 
-        assert_eq!(ic.dead.len(), 8);
+        assert_eq!(cells_that_may_be_born(&ic), 8);
         assert_eq!(n.count(), 0);
     }
 
@@ -90,7 +114,7 @@ mod tests {
 
         let expected = vec![Coord(1, 1)];
 
-        assert_eq!(ic.dead.len(), 12);
+        assert_eq!(cells_that_may_be_born(&ic), 12);
         assert_eq!(n, expected);
     }
 
@@ -101,7 +125,7 @@ mod tests {
 
         let mut ic2 = InterestingCells::new();
 
-        ic1.evolve_into(&mut ic2);
+        ic1.evolve_into(&mut ic2, &Rule::default());
 
         assert_eq!(ic2.alive.len(), 0);
     }
@@ -114,7 +138,7 @@ mod tests {
 
         let mut ic2 = InterestingCells::new();
 
-        ic1.evolve_into(&mut ic2);
+        ic1.evolve_into(&mut ic2, &Rule::default());
 
         assert_eq!(ic2.alive.len(), 0);
     }
@@ -132,7 +156,7 @@ mod tests {
 
         let mut ic2 = InterestingCells::new();
 
-        ic1.evolve_into(&mut ic2);
+        ic1.evolve_into(&mut ic2, &Rule::default());
 
         let mut expected = vec![Coord(0, 0), Coord(1, 0), Coord(0, 1), Coord(1, 1)];
 
@@ -153,7 +177,7 @@ mod tests {
 
         let mut ic2 = InterestingCells::new();
 
-        ic1.evolve_into(&mut ic2);
+        ic1.evolve_into(&mut ic2, &Rule::default());
 
         let mut expected = vec![Coord(1, 1)];
 
@@ -162,6 +186,507 @@ mod tests {
         assert_eq!(ic2.alive, expected);
     }
 
+    #[test]
+    fn record_births_and_deaths() {
+        let mut world = World::new();
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+            world.make_alive(Coord(1, 0));
+            world.make_alive(Coord(2, 0));
+        });
+
+        world.start_recording();
+        world.evolve().unwrap();
+
+        let entries = world.recorder().unwrap().entries();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].generation, 1);
+        assert_eq!(entries[1].population, 3);
+        assert_eq!(entries[1].births, 2);
+        assert_eq!(entries[1].deaths, 2);
+    }
+
+    #[test]
+    fn bounding_box_of_empty_world() {
+        let mut world = World::new();
+        world.finish();
+
+        assert_eq!(world.bounding_box(), None);
+    }
+
+    #[test]
+    fn bounding_box_follows_the_pattern() {
+        let mut world = World::new();
+        world.action(|world| {
+            world.make_alive(Coord(1, 0));
+            world.make_alive(Coord(2, 1));
+            world.make_alive(Coord(0, 2));
+            world.make_alive(Coord(1, 2));
+            world.make_alive(Coord(2, 2));
+        });
+
+        assert_eq!(world.bounding_box(), Some((Coord(0, 0), Coord(2, 2))));
+
+        for _ in 0..4 {
+            world.evolve().unwrap();
+        }
+
+        assert_eq!(world.bounding_box(), Some((Coord(1, 1), Coord(3, 3))));
+    }
+
+    #[test]
+    fn center_window_on_box() {
+        let mut window = Window::new(0, 0, 10, 4);
+        window.center_on(Coord(100, 50), Coord(102, 52));
+
+        assert_eq!((window.x, window.y), (96, 49));
+
+        let (min, max) = (common::Int::MIN, common::Int::MAX);
+
+        window.center_on(Coord(min, min), Coord(min + 1, min + 2));
+        assert_eq!((window.x, window.y), (min, min));
+
+        window.center_on(Coord(max - 2, max - 1), Coord(max, max));
+        assert_eq!((window.x, window.y), (max - 6, max - 3));
+
+        window.center_on(Coord(min, min), Coord(max, max));
+        assert_eq!((window.x, window.y), (-6, -3));
+    }
+
+    #[test]
+    fn generations_dying_cells_block_births() {
+        let mut world = World::with_rule("B2/S/C3".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+            world.make_alive(Coord(1, 0));
+        });
+
+        world.evolve().unwrap();
+
+        let window = Window::new(-5, -5, 10, 10);
+        let mut dying = vec![];
+        world.dying_cells(&window, &mut dying);
+
+        assert_eq!(
+            world.cells(),
+            &[Coord(0, -1), Coord(0, 1), Coord(1, -1), Coord(1, 1)]
+        );
+        assert_eq!(dying, vec![(Coord(0, 0), 2), (Coord(1, 0), 2)]);
+
+        world.evolve().unwrap();
+        dying.clear();
+        world.dying_cells(&window, &mut dying);
+
+        assert!(world.cells().binary_search(&Coord(0, 0)).is_err());
+        assert!(world.cells().binary_search(&Coord(1, 0)).is_err());
+        assert_eq!(dying.len(), 4);
+    }
+
+    #[test]
+    fn isotropic_rule_looks_at_neighboor_positions() {
+        let mut world = World::with_rule("B2i/S".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+            world.make_alive(Coord(0, 2));
+        });
+
+        world.evolve().unwrap();
+
+        // only the cell between them has them as opposite neighboors
+        assert_eq!(world.cells(), &[Coord(0, 1)]);
+    }
+
+    #[test]
+    fn von_neumann_rule_ignores_diagonals() {
+        let mut world = World::with_rule("B1/SV".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+        });
+
+        world.evolve().unwrap();
+
+        assert_eq!(
+            world.cells(),
+            &[Coord(-1, 0), Coord(0, -1), Coord(0, 1), Coord(1, 0)]
+        );
+    }
+
+    #[test]
+    fn larger_than_life_world() {
+        let mut world = World::with_rule("R2,C0,M0,S,B1..2,NM".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+        });
+
+        world.evolve().unwrap();
+
+        assert_eq!(world.population_size(), 24);
+        assert_eq!(world.bounding_box(), Some((Coord(-2, -2), Coord(2, 2))));
+    }
+
+    #[test]
+    fn glider_wraps_around_a_torus() {
+        let mut world = World::with_rule("B3/S23:T8,8".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(1, 0));
+            world.make_alive(Coord(2, 1));
+            world.make_alive(Coord(0, 2));
+            world.make_alive(Coord(1, 2));
+            world.make_alive(Coord(2, 2));
+        });
+
+        let start = world.cells().to_vec();
+
+        for _ in 0..32 {
+            world.evolve().unwrap();
+            assert_eq!(world.population_size(), 5);
+        }
+
+        assert_eq!(world.cells(), start.as_slice());
+    }
+
+    #[test]
+    fn accumulated_masks_match_lookups() {
+        let asymmetric = Neighboorhood::Custom(vec![(1, 2), (-2, 1), (0, -1)]);
+
+        let cases = [
+            (Neighboorhood::Moore, None),
+            (asymmetric, None),
+            (Neighboorhood::Moore, "K6*,6".parse().ok()),
+            (Neighboorhood::Hexagonal, "C6,6".parse().ok()),
+        ];
+
+        for (neighboorhood, grid) in cases {
+            let mut ic = InterestingCells::new();
+            ic.offsets = neighboorhood.offsets().to_vec();
+            ic.grid = grid;
+
+            for c in [(-3, -3), (-2, -3), (2, -3), (2, 2), (0, 0), (1, 0), (-3, 1)] {
+                ic.make_alive(Coord(c.0, c.1));
+            }
+
+            ic.finish();
+
+            for (c, mask) in ic.neighboor_masks() {
+                assert_eq!(mask, ic.live_neighboors_mask(c), "{:?} {:?}", c, grid);
+            }
+        }
+    }
+
+    #[test]
+    fn plane_clips_the_pattern() {
+        let mut world = World::with_rule("B3/S23:P4,4".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(-1, -2));
+            world.make_alive(Coord(0, -2));
+            world.make_alive(Coord(1, -2));
+            world.make_alive(Coord(10, 10));
+        });
+
+        assert_eq!(world.population_size(), 3);
+
+        world.evolve().unwrap();
+
+        // the blinker would need a cell above the top edge
+        assert_eq!(world.cells(), &[Coord(0, -2), Coord(0, -1)]);
+    }
+
+    #[test]
+    fn extend_alive_like_make_alive() {
+        let cells = (0..100).map(|i| Coord(i % 7, i / 7)).collect::<Vec<_>>();
+
+        let mut one_by_one = World::new();
+        one_by_one.action(|world| cells.iter().for_each(|c| world.make_alive(*c)));
+
+        let mut extended = World::new();
+        extended.action(|world| {
+            world.extend_alive(cells[..50].iter().copied());
+            world.extend_alive(cells[40..].iter().copied());
+        });
+
+        assert_eq!(extended.cells(), one_by_one.cells());
+        assert_eq!(extended.population_size(), 100);
+    }
+
+    #[test]
+    fn restore_a_snapshot() {
+        let mut world = World::with_rule("B013/S0124".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+            world.make_alive(Coord(1, 0));
+            world.make_alive(Coord(3, 2));
+        });
+
+        world.evolve().unwrap();
+        let snapshot = world.snapshot();
+
+        let mut evolved = vec![];
+        for _ in 0..4 {
+            world.evolve().unwrap();
+            evolved.push((world.cells().to_vec(), world.is_inverted()));
+        }
+
+        let mut restored = World::new();
+        restored.restore(snapshot);
+        assert_eq!(restored.gen(), 1);
+        assert!(restored.is_inverted());
+
+        for expected in evolved {
+            restored.evolve().unwrap();
+            assert_eq!(
+                (restored.cells().to_vec(), restored.is_inverted()),
+                expected
+            );
+        }
+
+        assert_eq!(restored.gen(), world.gen());
+    }
+
+    #[test]
+    fn glider_stops_at_the_edge_of_the_universe() {
+        let far = common::Int::MAX - 4;
+
+        let mut world = World::new();
+        world.action(|world| {
+            world.make_alive(Coord(far + 1, far));
+            world.make_alive(Coord(far + 2, far + 1));
+            world.make_alive(Coord(far, far + 2));
+            world.make_alive(Coord(far + 1, far + 2));
+            world.make_alive(Coord(far + 2, far + 2));
+        });
+
+        let error = loop {
+            if let Err(error) = world.evolve() {
+                break error;
+            }
+        };
+
+        assert_eq!(error.generation, world.gen());
+        let (_, max) = error.bounding_box;
+        assert_eq!(max.0.max(max.1), common::Int::MAX);
+        assert_eq!(world.population_size(), 5);
+        assert_eq!(world.evolve(), Err(error));
+    }
+
+    #[test]
+    fn b0_rule_inverts_every_other_generation() {
+        let mut world = World::with_rule("B013/S0124".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+        });
+
+        let window = Window::new(-2, -2, 5, 5);
+        let mut cells = Coords::new();
+
+        world.evolve().unwrap();
+        world.live_cells(&window, &mut cells);
+
+        // every cell is born or survives
+        assert!(world.is_inverted());
+        assert_eq!(cells.len(), 25);
+
+        world.evolve().unwrap();
+        cells.clear();
+        world.live_cells(&window, &mut cells);
+
+        // and then dies, with 8 live neighboors
+        assert!(!world.is_inverted());
+        assert!(cells.is_empty());
+        assert_eq!(world.population_size(), 0);
+    }
+
+    #[test]
+    fn live_population_of_inverted_worlds() {
+        let mut world = World::with_rule("B013/S0124:T10,10".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+        });
+
+        assert_eq!(world.live_population(), Some(1));
+
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+        assert_eq!(world.population_size(), 0);
+        assert_eq!(world.live_population(), Some(100));
+
+        world.set_rule("B013/S0124:T10,0".parse().unwrap());
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+        assert_eq!(world.live_population(), None);
+    }
+
+    #[test]
+    fn antilife_stays_inverted() {
+        let mut world = World::with_rule("B0123478/S01234678".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+            world.make_alive(Coord(1, 0));
+            world.make_alive(Coord(2, 0));
+        });
+
+        // the background is born around the blinker, which survives
+        world.evolve().unwrap();
+
+        assert!(world.is_inverted());
+        assert_eq!(world.population_size(), 0);
+        assert_eq!(world.live_population(), None);
+
+        world.evolve().unwrap();
+
+        let window = Window::new(-2, -2, 5, 5);
+        let mut cells = Coords::new();
+        world.live_cells(&window, &mut cells);
+
+        assert!(world.is_inverted());
+        assert_eq!(cells.len(), 25);
+    }
+
+    #[test]
+    fn engines_agree_on_a_soup() {
+        let soup = crate::search::SoupSearch::new(7).soup(0);
+
+        let mut sparse = World::new();
+        sparse.set_engine(Engine::Sparse);
+
+        let mut tiled = World::new();
+        assert_eq!(tiled.engine(), Engine::Tiled);
+
+        for world in [&mut sparse, &mut tiled] {
+            world.action(|world| {
+                for c in &soup {
+                    world.make_alive(*c);
+                }
+            });
+        }
+
+        for generation in 0..200 {
+            // the tiled engine has to hand over to the sparse one and back
+            if generation == 100 {
+                tiled.set_rule("B3/S23:T0,0".parse().unwrap());
+            } else if generation == 110 {
+                tiled.set_rule(Rule::default());
+            }
+
+            sparse.evolve().unwrap();
+            tiled.evolve().unwrap();
+
+            assert_eq!(sparse.cells(), tiled.cells());
+            assert_eq!(sparse.bounding_box(), tiled.bounding_box());
+        }
+    }
+
+    #[test]
+    fn engines_agree_across_rules() {
+        let soup = crate::search::SoupSearch::new(7).soup(1);
+
+        let mut sparse = World::new();
+        sparse.set_engine(Engine::Sparse);
+
+        let mut tiled = World::new();
+
+        for world in [&mut sparse, &mut tiled] {
+            world.action(|world| world.extend_alive(soup.iter().copied()));
+        }
+
+        for generation in 0..60 {
+            let rule = match generation {
+                20 => Some("B2/S"),
+                23 => Some("B36/S23"),
+                40 => Some("B3/S23"),
+                _ => None,
+            };
+
+            for world in [&mut sparse, &mut tiled] {
+                if let Some(rule) = rule {
+                    world.set_rule(rule.parse().unwrap());
+                }
+
+                world.evolve().unwrap();
+            }
+
+            assert_eq!(sparse.cells(), tiled.cells());
+        }
+
+        // a still life is left as it is by the tiled engine, unless the rule
+        // changes
+        let block = [Coord(0, 0), Coord(0, 1), Coord(1, 0), Coord(1, 1)];
+        let mut world = World::new();
+        world.action(|world| world.extend_alive(block.iter().copied()));
+        world.evolve().unwrap();
+        assert_eq!(world.cells(), &block[..]);
+
+        world.set_rule("B2/S".parse().unwrap());
+        world.evolve().unwrap();
+
+        assert_eq!(
+            world.cells(),
+            &[
+                Coord(-1, 0),
+                Coord(-1, 1),
+                Coord(0, -1),
+                Coord(0, 2),
+                Coord(1, -1),
+                Coord(1, 2),
+                Coord(2, 0),
+                Coord(2, 1),
+            ][..]
+        );
+    }
+
+    #[test]
+    fn tiled_cells_are_sorted_only_when_asked() {
+        let soup = crate::search::SoupSearch::new(11).soup(3);
+
+        let mut sparse = World::new();
+        sparse.set_engine(Engine::Sparse);
+
+        let mut tiled = World::new();
+
+        for world in [&mut sparse, &mut tiled] {
+            world.action(|world| world.extend_alive(soup.iter().copied()));
+
+            for _ in 0..50 {
+                world.evolve().unwrap();
+            }
+        }
+
+        assert_eq!(sparse.population_size(), tiled.population_size());
+        assert_eq!(sparse.bounding_box(), tiled.bounding_box());
+
+        let window = Window::new(-5, -7, 20, 30);
+        let (mut expected, mut cells) = (Coords::new(), Coords::new());
+        sparse.live_cells(&window, &mut expected);
+        tiled.live_cells(&window, &mut cells);
+        assert_eq!(cells, expected);
+
+        // editing picks up the cells from the tiles
+        for world in [&mut sparse, &mut tiled] {
+            world.action(|world| world.make_alive(Coord(-100, -100)));
+            world.evolve().unwrap();
+        }
+
+        assert_eq!(sparse.snapshot(), tiled.snapshot());
+    }
+
+    #[test]
+    fn changing_the_neighboorhood_of_a_loaded_world() {
+        let mut world = World::new();
+        world.action(|world| {
+            world.make_alive(Coord(0, 0));
+        });
+
+        world.set_rule("B1/SH".parse().unwrap());
+        world.evolve().unwrap();
+
+        // north-east and south-west are not hexagonal neighboors
+        assert_eq!(world.population_size(), 6);
+        assert!(!world.cells().contains(&Coord(1, -1)));
+        assert!(!world.cells().contains(&Coord(-1, 1)));
+    }
+
     #[test]
     fn window_of_an_empty_world() {
         let mut world = World::new();
@@ -208,80 +733,252 @@ mod tests {
 
         assert_eq!(cells, expected);
     }
+
+    #[test]
+    fn windows_at_the_limits_of_the_coordinates() {
+        let (min, max) = (common::Int::MIN, common::Int::MAX);
+
+        let mut world = World::with_rule("B2/S/C3".parse().unwrap());
+        world.action(|world| {
+            world.make_alive(Coord(max, min));
+            world.make_alive(Coord(max - 2, min + 4));
+            world.make_dying(Coord(max - 1, min), 2);
+            world.make_alive(Coord(min, max));
+        });
+
+        let window = Window::new(max - 2, min, 10, 5);
+
+        let mut cells = Coords::new();
+        world.live_cells(&window, &mut cells);
+        assert_eq!(cells, vec![Coord(max - 2, min + 4), Coord(max, min)]);
+
+        let mut dying = vec![];
+        world.dying_cells(&window, &mut dying);
+        assert_eq!(dying, vec![(Coord(max - 1, min), 2)]);
+
+        let window = Window::new(min, max, usize::MAX, usize::MAX);
+        cells.clear();
+        world.live_cells(&window, &mut cells);
+        assert_eq!(cells, vec![Coord(min, max)]);
+    }
+
+    #[test]
+    fn window_across_distant_tiles() {
+        let mut world = World::new();
+        world.action(|world| {
+            for i in -300..300 {
+                world.make_alive(Coord(i * 7, i * 13));
+                world.make_alive(Coord(i * 11, -i * 3));
+            }
+        });
+
+        let window = Window::new(-130, -200, 400, 350);
+
+        let mut cells = Coords::new();
+        world.live_cells(&window, &mut cells);
+
+        let expected = world
+            .cells()
+            .iter()
+            .filter(|c| c.0 >= -130 && c.0 < 270 && c.1 >= -200 && c.1 < 150)
+            .cloned()
+            .collect::<Coords>();
+
+        assert!(!expected.is_empty());
+        assert_eq!(cells, expected);
+
+        // queried again after evolving, with a window larger than the pattern
+        world.evolve().unwrap();
+
+        let window = Window::new(-10_000, -10_000, 20_000, 20_000);
+        cells.clear();
+        world.live_cells(&window, &mut cells);
+
+        assert_eq!(cells, world.cells());
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Copy, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Coord(pub common::Int, pub common::Int);
 
 pub type Coords = std::vec::Vec<Coord>;
 
-struct Neighbboors {
+struct Neighbboors<'a> {
     c: Coord,
+    offsets: &'a [(common::Int, common::Int)],
+    grid: Option<&'a BoundedGrid>,
 }
 
-impl Neighbboors {
-    fn iter(&self) -> NeighboorIter {
-        NeighboorIter::empty(self.c)
+impl<'a> Neighbboors<'a> {
+    fn iter(&self) -> NeighboorIter<'a> {
+        NeighboorIter::empty(self.c, self.offsets, self.grid)
     }
 }
 
+/// Neighboors wrap around the edges of a bounded grid, and the ones beyond
+/// the edges of a plane or of the universe are skipped
 #[derive(Debug, Clone)]
-struct NeighboorIter {
+struct NeighboorIter<'a> {
     c: Coord,
+    offsets: &'a [(common::Int, common::Int)],
+    grid: Option<&'a BoundedGrid>,
     i: usize,
 }
 
-impl NeighboorIter {
-    fn empty(c: Coord) -> Self {
-        Self { c, i: 0 }
+impl<'a> NeighboorIter<'a> {
+    fn empty(
+        c: Coord,
+        offsets: &'a [(common::Int, common::Int)],
+        grid: Option<&'a BoundedGrid>,
+    ) -> Self {
+        Self {
+            c,
+            offsets,
+            grid,
+            i: 0,
+        }
+    }
+
+    /// Index in the offsets of the last neighboor returned
+    fn position(&self) -> usize {
+        self.i - 1
     }
 }
 
-impl std::iter::Iterator for NeighboorIter {
+impl std::iter::Iterator for NeighboorIter<'_> {
     type Item = Coord;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.i == 8 {
-            return None;
+        loop {
+            let p = self.offsets.get(self.i)?;
+
+            self.i += 1;
+
+            // there's nothing beyond the limits of `common::Int`
+            let c = match (self.c.0.checked_add(p.0), self.c.1.checked_add(p.1)) {
+                (Some(x), Some(y)) => Coord(x, y),
+                _ => continue,
+            };
+
+            match self.grid {
+                None => return Some(c),
+                Some(grid) => {
+                    if let Some(c) = grid.wrap(c) {
+                        return Some(c);
+                    }
+                }
+            }
         }
+    }
+}
 
-        let n: [(i8, i8); 8] = [
-            (-1, -1),
-            (0, -1),
-            (1, -1),
-            (1, 0),
-            (1, 1),
-            (0, 1),
-            (-1, 1),
-            (-1, 0),
-        ];
+struct InterestingCells {
+    alive: Coords,
+    /// Cells decaying in Generations rules, sorted by `Coord`
+    dying: Vec<(Coord, u8)>,
+    y_range: Option<(common::Int, common::Int)>,
+    /// Neighboorhood of the rule being used
+    offsets: Vec<(common::Int, common::Int)>,
+    grid: Option<BoundedGrid>,
+    parallelism: Parallelism,
+    /// Built on the first window query after `alive` changes
+    index: OnceLock<TileIndex>,
+}
 
-        let p = n[self.i];
+/// Side of the tiles of `TileIndex`
+const INDEX_TILE_SIZE: common::Int = 64;
 
-        self.i += 1;
+/// Live cells grouped by square tiles, so that window queries take time
+/// proportional to the cells inside the window
+struct TileIndex {
+    tiles: HashMap<(common::Int, common::Int), Coords>,
+}
+
+impl TileIndex {
+    fn new(alive: &[Coord]) -> Self {
+        let mut tiles = HashMap::<_, Coords>::new();
+
+        for c in alive {
+            let tile = (
+                c.0.div_euclid(INDEX_TILE_SIZE),
+                c.1.div_euclid(INDEX_TILE_SIZE),
+            );
+            tiles.entry(tile).or_default().push(*c);
+        }
 
-        Some(Coord(
-            self.c.0 + p.0 as common::Int,
-            self.c.1 + p.1 as common::Int,
-        ))
+        TileIndex { tiles }
+    }
+
+    /// Cells inside the window, sorted by `Coord`
+    fn cells_in(&self, window: &Window) -> Coords {
+        let mut cells = Coords::new();
+
+        let (right, bottom) = match window.corners() {
+            Some((_, max)) => (max.0, max.1),
+            None => return cells,
+        };
+
+        let tiles_x = window.x.div_euclid(INDEX_TILE_SIZE)..=right.div_euclid(INDEX_TILE_SIZE);
+        let tiles_y = window.y.div_euclid(INDEX_TILE_SIZE)..=bottom.div_euclid(INDEX_TILE_SIZE);
+
+        let inside =
+            |c: &&Coord| c.0 >= window.x && c.0 <= right && c.1 >= window.y && c.1 <= bottom;
+
+        let window_tiles = tiles_x.clone().count() * tiles_y.clone().count();
+
+        if window_tiles > self.tiles.len() {
+            for (tile, tile_cells) in &self.tiles {
+                if tiles_x.contains(&tile.0) && tiles_y.contains(&tile.1) {
+                    cells.extend(tile_cells.iter().filter(inside));
+                }
+            }
+        } else {
+            for x in tiles_x {
+                for y in tiles_y.clone() {
+                    if let Some(tile_cells) = self.tiles.get(&(x, y)) {
+                        cells.extend(tile_cells.iter().filter(inside));
+                    }
+                }
+            }
+        }
+
+        cells.sort_unstable();
+        cells
     }
-}
-struct InterestingCells {
-    alive: Coords,
-    dead: Coords,
 }
 
 impl InterestingCells {
+    #[cfg(test)]
     fn live_neighboors<'a>(&'a self, c: Coord) -> impl Iterator<Item = Coord> + 'a {
-        neighboors(c)
+        neighboors(c, &self.offsets, self.grid.as_ref())
             .iter()
             .filter(move |c| self.alive.binary_search(c).is_ok())
     }
 
+    /// Bit i is set if the i-th neighboor (see `NeighboorIter`) is alive
+    fn live_neighboors_mask(&self, c: Coord) -> u64 {
+        let mut n = neighboors(c, &self.offsets, self.grid.as_ref()).iter();
+        let mut mask = 0;
+
+        while let Some(c) = n.next() {
+            if self.alive.binary_search(&c).is_ok() {
+                mask |= 1 << n.position();
+            }
+        }
+
+        mask
+    }
+
     fn new() -> Self {
         InterestingCells {
             alive: Coords::with_capacity(1000),
-            dead: Coords::with_capacity(1000 * 8),
+            dying: Vec::new(),
+            y_range: None,
+            offsets: Neighboorhood::Moore.offsets().to_vec(),
+            grid: None,
+            parallelism: Parallelism::new(),
+            index: OnceLock::new(),
         }
     }
 
@@ -290,57 +987,174 @@ impl InterestingCells {
         self
     }
 
+    fn extend_alive(&mut self, cells: impl Iterator<Item = Coord>) {
+        self.alive.reserve(cells.size_hint().0);
+        self.alive.extend(cells);
+    }
+
+    fn make_dying(&mut self, c: Coord, state: u8) -> &mut InterestingCells {
+        self.dying.push((c, state));
+        self
+    }
+
     fn finish(&mut self) {
-        self.make_dead_from_alive();
-        let dead = &mut self.dead;
-        let alive = &mut self.alive;
+        if let Some(grid) = &self.grid {
+            self.alive.retain(|c| grid.contains(*c));
+            self.dying.retain(|d| grid.contains(d.0));
+        }
+
+        self.parallelism
+            .sort_unstable_by_key(&mut self.dying, |d| d.0);
+        self.parallelism.sort_unstable(&mut self.alive);
+        self.alive.dedup();
+
+        self.update_y_range();
+    }
 
-        alive.par_sort_unstable();
+    /// Alive is sorted by x, so only the y range needs to be kept. Also
+    /// drops the window index, which is out of date by now
+    fn update_y_range(&mut self) {
+        self.index = OnceLock::new();
+
+        self.y_range = self.alive.iter().fold(None, |range, c| match range {
+            None => Some((c.1, c.1)),
+            Some((min, max)) => Some((min.min(c.1), max.max(c.1))),
+        });
+    }
 
-        dead.par_sort_unstable();
-        dead.dedup();
-        dead.retain(|c| alive.binary_search(c).is_err());
+    fn bounding_box(&self) -> Option<(Coord, Coord)> {
+        match (self.alive.first(), self.alive.last(), self.y_range) {
+            (Some(first), Some(last), Some((min_y, max_y))) => {
+                Some((Coord(first.0, min_y), Coord(last.0, max_y)))
+            }
+            _ => None,
+        }
     }
 
     fn len(&self) -> usize {
         self.alive.len()
     }
 
-    fn make_dead_from_alive(&mut self) {
-        let alive = &self.alive;
-        let dead = &mut self.dead;
-
-        dead.extend(alive.iter().flat_map(|c| {
-            neighboors(*c)
+    /// Every cell with a live neighboor, and the live cells themselves, with
+    /// the mask of their live neighboors (see `live_neighboors_mask`), sorted
+    /// by `Coord`.
+    ///
+    /// Each live cell adds its bit to the cells it is a neighboor of, so that
+    /// the masks are accumulated in a single pass with no lookups. On twisted
+    /// grids the cells near the edges see their neighboors reflected, so their
+    /// masks are computed by looking the neighboors up instead
+    fn neighboor_masks(&self) -> Vec<(Coord, u64)> {
+        let offsets = &self.offsets;
+        let grid = self.grid.as_ref();
+        let parallelism = &self.parallelism;
+
+        let mut masks = parallelism.flat_map(&self.alive, |c| {
+            let c = *c;
+
+            offsets
                 .iter()
-                .filter(|c| alive.binary_search(c).is_err())
-        }));
+                .enumerate()
+                .filter_map(move |(i, o)| {
+                    let n = Coord(c.0.checked_sub(o.0)?, c.1.checked_sub(o.1)?);
+
+                    match grid {
+                        None => Some((n, 1 << i)),
+                        Some(grid) => grid.wrap(n).map(|n| (n, 1 << i)),
+                    }
+                })
+                .chain(std::iter::once((c, 0)))
+        });
+
+        let twisted = grid.filter(|grid| grid.is_twisted());
+
+        if let Some(grid) = twisted {
+            masks.extend(parallelism.flat_map(&self.alive, |c| {
+                neighboors(*c, offsets, Some(grid)).iter().map(|n| (n, 0))
+            }));
+        }
+
+        parallelism.sort_unstable_by_key(&mut masks, |m| m.0);
+
+        let mut merged = Vec::<(Coord, u64)>::with_capacity(masks.len() / 2);
 
-        dead.par_sort_unstable();
-        dead.dedup();
+        for (c, mask) in masks {
+            match merged.last_mut() {
+                Some(last) if last.0 == c => last.1 |= mask,
+                _ => merged.push((c, mask)),
+            }
+        }
+
+        if let Some(grid) = twisted {
+            parallelism.for_each_mut(&mut merged, |(c, mask)| {
+                let near_edge = offsets
+                    .iter()
+                    .any(|o| !grid.contains(Coord(c.0 + o.0, c.1 + o.1)));
+
+                if near_edge {
+                    *mask = self.live_neighboors_mask(*c);
+                }
+            });
+        }
+
+        merged
     }
 
-    fn evolve_into(&self, e: &mut InterestingCells) {
+    fn evolve_into(&self, e: &mut InterestingCells, rule: &Rule) {
         e.alive.clear();
-        e.dead.clear();
+        e.dying.clear();
+
+        if let Neighboorhood::Range { .. } = rule.neighboorhood() {
+            let (alive, dying) = ltl::evolve(&self.alive, &self.dying, rule, &self.parallelism);
+            e.alive = alive;
+            e.dying = dying;
+            e.update_y_range();
+            return;
+        }
 
-        let alive = &self.alive;
-        let dead = &self.dead;
+        // both lists are sorted, so the state of each cell is found by
+        // walking them along with the masks
+        let mut alive = self.alive.iter().peekable();
+        let mut dying = self.dying.iter().peekable();
 
-        e.alive.par_extend(alive.into_par_iter().filter(|c| {
-            mutate(CellState::Alive, self.live_neighboors(**c).count()) == CellState::Alive
-        }));
+        for (c, mask) in self.neighboor_masks() {
+            while alive.next_if(|a| **a < c).is_some() {}
 
-        e.alive.par_extend(dead.into_par_iter().filter(|c| {
-            mutate(CellState::Dead, self.live_neighboors(**c).count()) == CellState::Alive
-        }));
+            let state = if alive.next_if(|a| **a == c).is_some() {
+                CellState::Alive
+            } else {
+                while dying.next_if(|d| d.0 < c).is_some() {}
 
-        e.alive.par_sort_unstable();
+                // dying cells decay on their own, below
+                if dying.peek().is_some_and(|d| d.0 == c) {
+                    continue;
+                }
 
-        e.make_dead_from_alive();
+                CellState::Dead
+            };
+
+            match rule.mutate(state, mask) {
+                CellState::Alive => e.alive.push(c),
+                CellState::Dying(s) => e.dying.push((c, s)),
+                CellState::Dead => {}
+            }
+        }
+
+        if !self.dying.is_empty() {
+            e.dying.extend(self.dying.iter().filter_map(|(c, s)| {
+                match rule.mutate(CellState::Dying(*s), 0) {
+                    CellState::Dying(s) => Some((*c, s)),
+                    _ => None,
+                }
+            }));
+
+            self.parallelism.sort_unstable_by_key(&mut e.dying, |d| d.0);
+        }
+
+        e.update_y_range();
     }
 }
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Window {
     pub w: usize,
     pub h: usize,
@@ -352,13 +1166,90 @@ impl Window {
     pub fn new(x: common::Int, y: common::Int, w: usize, h: usize) -> Self {
         Window { w, h, x, y }
     }
+
+    /// Top-left and bottom-right cells (inclusive), if it has any. Windows
+    /// reaching past the limits of `common::Int` end at them
+    fn corners(&self) -> Option<(Coord, Coord)> {
+        if self.w == 0 || self.h == 0 {
+            return None;
+        }
+
+        let last = |start: common::Int, len: usize| {
+            let len = common::Int::try_from(len - 1).unwrap_or(common::Int::MAX);
+            start.saturating_add(len)
+        };
+
+        let max = Coord(last(self.x, self.w), last(self.y, self.h));
+
+        Some((Coord(self.x, self.y), max))
+    }
+
+    /// Moves the window so that the box between `min` and `max` (inclusive)
+    /// is at its center
+    pub fn center_on(&mut self, min: Coord, max: Coord) {
+        // the box can span the whole range of `common::Int`
+        let half =
+            |min: common::Int, max: common::Int| ((max as i64 - min as i64) / 2) as common::Int;
+        let center_x = min.0 + half(min.0, max.0);
+        let center_y = min.1 + half(min.1, max.1);
+
+        let half_w = common::Int::try_from(self.w / 2).unwrap_or(common::Int::MAX);
+        let half_h = common::Int::try_from(self.h / 2).unwrap_or(common::Int::MAX);
+
+        self.x = center_x.saturating_sub(half_w);
+        self.y = center_y.saturating_sub(half_h);
+    }
 }
 
+/// The pattern reached the edge of the universe
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverflowError {
+    pub generation: usize,
+    pub bounding_box: (Coord, Coord),
+}
+
+impl fmt::Display for OverflowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (min, max) = self.bounding_box;
+        write!(
+            f,
+            "pattern from ({}, {}) to ({}, {}) reached the edge of the universe at generation {}",
+            min.0, min.1, max.0, max.1, self.generation
+        )
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
 pub struct World {
     set1: InterestingCells,
     set2: InterestingCells,
     using_set1: bool,
     generation: usize,
+    rule: Rule,
+    /// Set for rules with B0, with `inverted` telling if the cells currently
+    /// stored are the dead ones
+    emulation: Option<B0Emulation>,
+    inverted: bool,
+    engine: Engine,
+    /// The current cells, while the tiled engine is in use
+    tiled: Option<TiledUniverse>,
+    /// Whether `tiled` is ahead of the current set, which is then stale. Its
+    /// cells are only sorted into `tiled_cells` when asked for
+    tiled_ahead: bool,
+    tiled_cells: OnceLock<Coords>,
+    recorder: Option<PopulationRecorder>,
+}
+
+/// How `World::evolve` computes the next generation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Engine {
+    /// Sorted list of live cells, accumulating the neighboors of each of them
+    /// in a single pass, for any rule
+    Sparse,
+    /// `TiledUniverse`, much faster on busy patterns, falling back to
+    /// `Engine::Sparse` for the rules it doesn't support
+    Tiled,
 }
 
 pub trait CellStorage {
@@ -371,6 +1262,12 @@ impl CellStorage for Coords {
     }
 }
 
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl World {
     pub fn new() -> Self {
         World {
@@ -378,6 +1275,14 @@ impl World {
             set2: InterestingCells::new(),
             using_set1: true,
             generation: 0,
+            rule: Rule::default(),
+            emulation: None,
+            inverted: false,
+            engine: Engine::Tiled,
+            tiled: None,
+            tiled_ahead: false,
+            tiled_cells: OnceLock::new(),
+            recorder: None,
         }
     }
 
@@ -397,15 +1302,116 @@ impl World {
         }
     }
 
+    /// The tiled universe, if it's ahead of the current set
+    fn tiled_ahead(&self) -> Option<&TiledUniverse> {
+        self.tiled.as_ref().filter(|_| self.tiled_ahead)
+    }
+
+    /// Brings the current set up to date with the tiled universe
+    fn sync_tiled(&mut self) {
+        let cells = match (self.tiled_cells.take(), self.tiled_ahead()) {
+            (_, None) => return,
+            (Some(cells), _) => cells,
+            (None, Some(tiled)) => tiled.cells(),
+        };
+
+        self.tiled_ahead = false;
+
+        let current = self.working_sets().0;
+        current.alive = cells;
+        current.dying.clear();
+        current.update_y_range();
+    }
+
+    pub fn with_rule(rule: Rule) -> Self {
+        let mut world = World::new();
+        world.set_rule(rule);
+        world
+    }
+
+    pub fn rule(&self) -> &Rule {
+        &self.rule
+    }
+
+    /// The current cells are taken as the live ones, even if they were inverted
+    pub fn set_rule(&mut self, rule: Rule) {
+        // Larger than Life rules count neighboors on a grid of their own
+        let offsets = match rule.neighboorhood() {
+            Neighboorhood::Range { .. } => &[],
+            neighboorhood => neighboorhood.offsets(),
+        };
+
+        let grid = rule.bounded_grid().copied();
+
+        self.sync_tiled();
+
+        // the tiled universe only steps the tiles that changed under the
+        // previous rule
+        if rule != self.rule {
+            self.tiled = None;
+        }
+
+        if self.set1.offsets != offsets || self.set1.grid != grid {
+            for set in [&mut self.set1, &mut self.set2] {
+                set.offsets = offsets.to_vec();
+                set.grid = grid;
+            }
+
+            self.tiled = None;
+            self.working_sets().0.finish();
+        }
+
+        self.emulation = rule.b0_emulation();
+        self.inverted = false;
+        self.rule = rule;
+    }
+
     pub fn make_alive(&mut self, c: Coord) {
+        self.sync_tiled();
         self.working_sets().0.make_alive(c);
     }
 
+    /// Like `make_alive` for many cells, reserving room for them at once
+    pub fn extend_alive<I: IntoIterator<Item = Coord>>(&mut self, cells: I) {
+        self.sync_tiled();
+        self.working_sets().0.extend_alive(cells.into_iter());
+    }
+
+    /// Sets a cell in one of the decaying states of a Generations rule
+    pub fn make_dying(&mut self, c: Coord, state: u8) {
+        self.sync_tiled();
+        self.working_sets().0.make_dying(c, state);
+    }
+
     fn finish(&mut self) {
+        self.sync_tiled();
+        self.tiled = None;
         self.working_sets().0.finish();
     }
 
-    pub fn action<F: Fn(&mut World)>(&mut self, f: F) {
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    pub fn parallelism(&self) -> &Parallelism {
+        &self.set1.parallelism
+    }
+
+    /// How the engines split their work across threads
+    pub fn set_parallelism(&mut self, parallelism: Parallelism) {
+        if let Some(tiled) = &mut self.tiled {
+            tiled.set_parallelism(parallelism.clone());
+        }
+
+        self.set1.parallelism = parallelism.clone();
+        self.set2.parallelism = parallelism;
+    }
+
+    pub fn action<F: FnOnce(&mut World)>(&mut self, f: F) {
         f(self);
         self.finish();
     }
@@ -414,73 +1420,347 @@ impl World {
         self.using_set1 = !self.using_set1;
     }
 
-    pub fn evolve(&mut self) {
-        let working_sets = self.working_sets();
-        working_sets.0.evolve_into(working_sets.1);
+    /// Fails, leaving the world as it is, if the next generation could have
+    /// cells beyond the limits of `common::Int`
+    pub fn evolve(&mut self) -> Result<(), OverflowError> {
+        if let Some((min, max)) = self.bounding_box() {
+            let reach = self
+                .rule
+                .neighboorhood()
+                .offsets()
+                .iter()
+                .map(|o| o.0.abs().max(o.1.abs()))
+                .max()
+                .unwrap_or(0);
+
+            let (lowest, highest) = (common::Int::MIN + reach, common::Int::MAX - reach);
+
+            if min.0 < lowest || min.1 < lowest || max.0 > highest || max.1 > highest {
+                return Err(OverflowError {
+                    generation: self.generation,
+                    bounding_box: (min, max),
+                });
+            }
+        }
+
+        let tiled = self.engine == Engine::Tiled && TiledUniverse::supports(self.next_rule());
+
+        if !tiled {
+            self.sync_tiled();
+        }
+
+        let (current, next) = if self.using_set1 {
+            (&mut self.set1, &mut self.set2)
+        } else {
+            (&mut self.set2, &mut self.set1)
+        };
+
+        let rule = match &self.emulation {
+            None => &self.rule,
+            Some(emulation) if self.inverted => {
+                self.inverted = emulation.stays_inverted;
+                &emulation.from_inverted
+            }
+            Some(emulation) => {
+                self.inverted = true;
+                &emulation.from_normal
+            }
+        };
+
+        if tiled {
+            let tiled = self.tiled.get_or_insert_with(|| {
+                let mut tiled = TiledUniverse::from_cells(&current.alive);
+                tiled.set_parallelism(current.parallelism.clone());
+                tiled
+            });
+
+            tiled.step(rule);
+
+            // the cells are sorted out of the tiles only when asked for
+            next.alive.clear();
+            next.dying.clear();
+            next.update_y_range();
+
+            self.tiled_ahead = true;
+            self.tiled_cells = OnceLock::new();
+        } else {
+            self.tiled = None;
+            current.evolve_into(next, rule);
+        }
+
         self.swap_sets();
         self.generation += 1;
+
+        if self.recorder.is_some() {
+            self.sync_tiled();
+            self.record();
+        }
+
+        Ok(())
+    }
+
+    /// The rule that computes the next generation, which is one of the
+    /// emulation ones for rules with B0
+    fn next_rule(&self) -> &Rule {
+        match &self.emulation {
+            None => &self.rule,
+            Some(emulation) if self.inverted => &emulation.from_inverted,
+            Some(emulation) => &emulation.from_normal,
+        }
+    }
+
+    /// Starts keeping per-generation statistics, beginning with the current one
+    pub fn start_recording(&mut self) {
+        // births and deaths are counted on the cells of the sets
+        self.sync_tiled();
+
+        let mut recorder = PopulationRecorder::new();
+        recorder.record(GenerationStats {
+            generation: self.generation,
+            population: self.population_size(),
+            births: 0,
+            deaths: 0,
+            bounding_box: self.bounding_box(),
+        });
+        self.recorder = Some(recorder);
+    }
+
+    pub fn stop_recording(&mut self) -> Option<PopulationRecorder> {
+        self.recorder.take()
     }
 
+    pub fn recorder(&self) -> Option<&PopulationRecorder> {
+        self.recorder.as_ref()
+    }
+
+    fn record(&mut self) {
+        let (previous, current) = if self.using_set1 {
+            (&self.set2.alive, &self.set1.alive)
+        } else {
+            (&self.set1.alive, &self.set2.alive)
+        };
+
+        let kept = count_common(previous, current);
+
+        let stats = GenerationStats {
+            generation: self.generation,
+            population: current.len(),
+            births: current.len() - kept,
+            deaths: previous.len() - kept,
+            bounding_box: self.bounding_box(),
+        };
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(stats);
+        }
+    }
+
+    /// Top-left and bottom-right corners (inclusive) of the live pattern
+    pub fn bounding_box(&self) -> Option<(Coord, Coord)> {
+        match self.tiled_ahead() {
+            Some(tiled) => tiled.bounding_box(),
+            None => self.current_set().bounding_box(),
+        }
+    }
+
+    /// Number of cells stored, which are the dead ones if `is_inverted`
     pub fn population_size(&self) -> usize {
-        self.current_set().len()
+        match self.tiled_ahead() {
+            Some(tiled) => tiled.population(),
+            None => self.current_set().len(),
+        }
+    }
+
+    /// Number of live cells, even if the stored cells are inverted. None
+    /// if infinitely many cells are alive, as when the cells are inverted on
+    /// an unbounded grid
+    pub fn live_population(&self) -> Option<usize> {
+        if !self.inverted {
+            return Some(self.population_size());
+        }
+
+        let area = self.rule.bounded_grid()?.area()?;
+        Some(area - self.population_size())
+    }
+
+    /// Whether the stored cells are the dead ones, as happens to rules with
+    /// B0 every other generation (see `Rule::b0_emulation`)
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// All live cells (or dead ones if `is_inverted`), sorted by `Coord`
+    pub fn cells(&self) -> &[Coord] {
+        match self.tiled_ahead() {
+            Some(tiled) => self.tiled_cells.get_or_init(|| tiled.cells()),
+            None => &self.current_set().alive,
+        }
+    }
+
+    /// Cells decaying in Generations rules, sorted by `Coord`
+    pub fn dying(&self) -> &[(Coord, u8)] {
+        &self.current_set().dying
     }
 
     pub fn gen(&self) -> usize {
         self.generation
     }
 
-    // FIXME: this method is very unoptimized
-    pub fn live_cells(&self, window: &Window, cells: &mut CellStorage) {
-        let alive = &self.current_set().alive;
+    /// Starts counting generations from `generation`, as for patterns saved
+    /// at a later generation
+    pub fn set_gen(&mut self, generation: usize) {
+        self.generation = generation;
+    }
 
-        let find_index = |c: Coord| match alive.binary_search(&c) {
-            Ok(index) => index,
-            Err(index) => index,
-        };
+    /// The generation, rule and cells, to resume the simulation later
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            generation: self.generation,
+            rule: self.rule.clone(),
+            inverted: self.inverted,
+            cells: self.cells().to_vec(),
+            dying: self.dying().to_vec(),
+        }
+    }
 
-        let lower_index = find_index(Coord(window.x - 1, window.y));
-        let upper_index = find_index(Coord(
-            window.x + window.w as common::Int,
-            window.y + window.h as common::Int + 1,
-        ));
+    /// Replaces the generation, rule and cells by the ones of the snapshot.
+    /// The engine, parallelism and recorder are kept, without the entries of
+    /// the recorder after the generation of the snapshot
+    pub fn restore(&mut self, snapshot: WorldSnapshot) {
+        let WorldSnapshot {
+            generation,
+            rule,
+            inverted,
+            cells,
+            dying,
+        } = snapshot;
+
+        // the snapshot replaces whatever the tiled universe is ahead with
+        self.tiled = None;
+        self.tiled_ahead = false;
+
+        self.set_rule(rule);
+
+        for set in [&mut self.set1, &mut self.set2] {
+            set.alive.clear();
+            set.dying.clear();
+        }
 
-        assert!(lower_index <= upper_index);
+        self.action(|world| {
+            world.extend_alive(cells);
 
-        let slice = &alive[lower_index..upper_index];
+            for (c, state) in dying {
+                world.make_dying(c, state);
+            }
+        });
 
-        slice
-            .iter()
-            .filter(|c| {
-                (c.0 >= window.x)
-                    && (c.0 < window.x + window.w as common::Int)
-                    && (c.1 >= window.y)
-                    && (c.1 < window.y + window.h as common::Int)
-            })
-            .for_each(|c| {
-                cells.add_cell(*c);
-            });
+        self.inverted = inverted;
+        self.generation = generation;
+
+        if let Some(recorder) = &mut self.recorder {
+            recorder.truncate(generation);
+        }
     }
-}
 
-#[derive(Debug, PartialEq)]
-enum CellState {
-    Dead,
-    Alive,
+    /// Makes a dead cell alive, or a live or dying one dead, as in an editor
+    pub fn toggle_cell(&mut self, c: Coord) {
+        if self
+            .rule
+            .bounded_grid()
+            .is_some_and(|grid| !grid.contains(c))
+        {
+            return;
+        }
+
+        self.sync_tiled();
+        self.tiled = None;
+
+        let current = self.working_sets().0;
+        let dying = current.dying.len();
+        current.dying.retain(|d| d.0 != c);
+
+        match current.alive.binary_search(&c) {
+            Ok(i) => {
+                current.alive.remove(i);
+            }
+            Err(_) if current.dying.len() != dying => {}
+            Err(i) => current.alive.insert(i, c),
+        }
+
+        current.update_y_range();
+    }
+
+    /// Live cells inside the window, sorted by `Coord`, even if the stored
+    /// cells are inverted
+    pub fn live_cells(&self, window: &Window, cells: &mut dyn CellStorage) {
+        let stored = match (self.tiled_ahead(), window.corners()) {
+            (Some(tiled), Some((min, max))) => tiled.cells_in(min, max),
+            (Some(_), None) => Coords::new(),
+            (None, _) => {
+                let current = self.current_set();
+                let index = current.index.get_or_init(|| TileIndex::new(&current.alive));
+                index.cells_in(window)
+            }
+        };
+
+        if !self.inverted {
+            stored.into_iter().for_each(|c| cells.add_cell(c));
+            return;
+        }
+
+        let (min, max) = match window.corners() {
+            Some(corners) => corners,
+            None => return,
+        };
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                if stored.binary_search(&Coord(x, y)).is_err() {
+                    cells.add_cell(Coord(x, y));
+                }
+            }
+        }
+    }
+
+    /// Cells in the decaying states of a Generations rule, with their states
+    pub fn dying_cells(&self, window: &Window, cells: &mut Vec<(Coord, u8)>) {
+        let (min, max) = match window.corners() {
+            Some(corners) => corners,
+            None => return,
+        };
+
+        cells.extend(
+            self.current_set()
+                .dying
+                .iter()
+                .filter(|(c, _)| c.0 >= min.0 && c.0 <= max.0 && c.1 >= min.1 && c.1 <= max.1),
+        );
+    }
 }
 
-fn mutate(state: CellState, neighboors: usize) -> CellState {
-    match state {
-        CellState::Dead => match neighboors {
-            3 => CellState::Alive,
-            _ => CellState::Dead,
-        },
-        CellState::Alive => match neighboors {
-            2 | 3 => CellState::Alive,
-            _ => CellState::Dead,
-        },
+/// Number of cells present in both sorted slices
+fn count_common(a: &[Coord], b: &[Coord]) -> usize {
+    let (mut i, mut j, mut count) = (0, 0, 0);
+
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                count += 1;
+                i += 1;
+                j += 1;
+            }
+        }
     }
+
+    count
 }
 
-fn neighboors(c: Coord) -> Neighbboors {
-    Neighbboors { c }
+fn neighboors<'a>(
+    c: Coord,
+    offsets: &'a [(common::Int, common::Int)],
+    grid: Option<&'a BoundedGrid>,
+) -> Neighbboors<'a> {
+    Neighbboors { c, offsets, grid }
 }